@@ -0,0 +1,176 @@
+//! Measures the time to draw a tile map with four layers into the tile map buffer, compared to
+//! drawing each layer into its own render target on every frame.
+//!
+//! Run with `cargo bench -p orbgame-api`.
+
+use std::time::{Duration, Instant};
+
+use orbgame_api::{
+    prelude::*,
+    render::{Image, RenderTarget},
+    utils::*,
+};
+
+const FRAMES: u32 = 200;
+const SIZE: (u32, u32) = (800, 600);
+
+fn map(column_count: usize, row_count: usize) -> Map {
+    let tiles = |offset: usize| {
+        (0..column_count * row_count)
+            .map(|i| ((i + offset) % 64) as i32)
+            .collect()
+    };
+
+    Map {
+        layer_count: 4,
+        row_count,
+        column_count,
+        tile_size: 32,
+        layers: (0..4)
+            .map(|l| Layer::new(format!("layer_{}", l), tiles(l)))
+            .collect(),
+        ..Map::default()
+    }
+}
+
+fn camera(i: u32, size: (u32, u32)) -> Camera {
+    CameraBuilder::new()
+        .x(i as f64)
+        .y(i as f64 / 2.0)
+        .width(size.0 as f64)
+        .height(size.1 as f64)
+        .build()
+}
+
+fn bench<F: FnMut(u32)>(name: &str, mut frame: F) {
+    let start = Instant::now();
+
+    for i in 0..FRAMES {
+        frame(i);
+    }
+
+    let elapsed: Duration = start.elapsed();
+    println!(
+        "{:<40} {:>10.3} ms per frame",
+        name,
+        elapsed.as_secs_f64() * 1000.0 / FRAMES as f64
+    );
+}
+
+// Draws a premultiplied argb pixel over another one like the render context.
+fn blend(source: u32, destination: u32) -> u32 {
+    let alpha = source >> 24;
+
+    if alpha == 255 {
+        return source;
+    }
+
+    let channel = |shift: u32| {
+        let s = (source >> shift) & 0xFF;
+        let d = (destination >> shift) & 0xFF;
+
+        (s + (d * (255 - alpha) + 127) / 255).min(255) << shift
+    };
+
+    channel(24) | channel(16) | channel(8) | channel(0)
+}
+
+// Draws a frame like the render object before the buffer: the map is cloned from the widget, each
+// layer is drawn into a new render target of the full size and each render target is drawn over
+// the frame. The maps hold one visible layer each.
+fn frame_per_layer(map: &Map, layer_maps: &[Map], image: &SharedImage, i: u32) {
+    let _map = map.clone();
+    let camera = camera(i, SIZE);
+    let mut frame = RenderTarget::new(SIZE.0, SIZE.1);
+
+    for layer_map in layer_maps {
+        let layer = render_map(layer_map, &camera, Some(image), &TileImages::default(), 0.0);
+
+        for (destination, source) in frame.data_mut().iter_mut().zip(layer.data()) {
+            *destination = blend(*source, *destination);
+        }
+    }
+}
+
+// Draws a frame like the render object of the tile map, which clones the shared properties of
+// the widget.
+fn frame(
+    map: &SharedMap,
+    image: &SharedImage,
+    images: &TileImages,
+    buffer: &TileMapBuffer,
+    i: u32,
+    size: (u32, u32),
+) {
+    let (map, image, images) = (map.clone(), image.clone(), images.clone());
+
+    buffer.update(
+        &map.borrow(),
+        &camera(i, size),
+        Some(&image),
+        &images,
+        0.0,
+        size,
+    );
+}
+
+fn main() {
+    let mut map = map(100, 100);
+    let image = SharedImage::new(Image::from_data(256, 256, vec![0xFF80_8080; 256 * 256]).unwrap());
+    let images = TileImages::default();
+
+    // a new render target for every layer and frame, like the render object before the buffer
+    let layer_maps: Vec<Map> = (0..map.layers.len())
+        .map(|l| {
+            let mut layer_map = map.clone();
+
+            for (i, layer) in layer_map.layers.iter_mut().enumerate() {
+                layer.visible = i == l;
+            }
+
+            layer_map
+        })
+        .collect();
+    bench("render target per layer and frame", |i| {
+        frame_per_layer(&map, &layer_maps, &image, i);
+    });
+
+    // the chunks are reused and only composited again because the camera moves
+    let buffer = TileMapBuffer::new();
+    bench("reused buffer, moving camera", |i| {
+        buffer.update(&map, &camera(i, SIZE), Some(&image), &images, 0.0, SIZE);
+    });
+
+    // nothing changes, the cached render target is used
+    let buffer = TileMapBuffer::new();
+    bench("reused buffer, still camera", |_| {
+        buffer.update(&map, &camera(0, SIZE), Some(&image), &images, 0.0, SIZE);
+    });
+
+    // one tile changes every frame, only its chunk is rendered again
+    let buffer = TileMapBuffer::new();
+    bench("reused buffer, changed tile", |i| {
+        map.set_tile(0, 5, 5, (i % 64) as i32);
+        buffer.update(&map, &camera(0, SIZE), Some(&image), &images, 0.0, SIZE);
+    });
+
+    // the frame time stays the same for larger maps
+    for &tiles in &[64, 128, 256] {
+        let map = SharedMap::new(self::map(tiles, tiles));
+        let buffer = TileMapBuffer::new();
+        bench(&format!("map {} x {}, moving camera", tiles, tiles), |i| {
+            frame(&map, &image, &images, &buffer, i, SIZE);
+        });
+    }
+
+    // and grows with the visible tiles
+    let map = SharedMap::new(self::map(256, 256));
+
+    for &size in &[(200, 150), (400, 300), (800, 600), (1600, 1200)] {
+        let buffer = TileMapBuffer::new();
+        bench(
+            &format!("view {} x {}, moving camera", size.0, size.1),
+            |i| frame(&map, &image, &images, &buffer, i, size),
+        );
+    }
+}
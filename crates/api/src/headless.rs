@@ -0,0 +1,334 @@
+use std::{env, error::Error, fmt, fs::File, io::BufWriter, path::Path};
+
+use crate::{
+    render::RenderTarget,
+    render_object::TileMapRenderObject,
+    tile_images::{SharedImage, TileImages},
+    utils::{Camera, Map},
+};
+
+/// The environment variable that makes `assert_golden` write the reference images instead of
+/// comparing them.
+pub const UPDATE_GOLDEN: &str = "ORBGAME_UPDATE_GOLDEN";
+
+/// Describes the errors that could occur on writing, reading or comparing images.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageError {
+    /// The image file could not be read or written.
+    Io { path: String, message: String },
+
+    /// The image could not be encoded as PNG.
+    Encode(String),
+
+    /// The PNG file could not be decoded.
+    Decode(String),
+
+    /// The size of the image differs from the size of the reference image.
+    Size {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+
+    /// Pixels differ more than the tolerance from the reference image.
+    Mismatch { count: usize, x: u32, y: u32 },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io { path, message } => write!(f, "could not access {}: {}", path, message),
+            ImageError::Encode(message) => write!(f, "could not encode image: {}", message),
+            ImageError::Decode(message) => write!(f, "could not decode image: {}", message),
+            ImageError::Size { expected, actual } => write!(
+                f,
+                "expected size {}x{}, found {}x{}",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            ImageError::Mismatch { count, x, y } => write!(
+                f,
+                "{} pixels differ from the reference, the first at {}, {}",
+                count, x, y
+            ),
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+pub(crate) fn io_error(path: &Path, error: impl fmt::Display) -> ImageError {
+    ImageError::Io {
+        path: path.display().to_string(),
+        message: error.to_string(),
+    }
+}
+
+/// Renders the image layers and all visible layers of the map through the camera into a new
+/// render target with the size of the camera. Uses the same blitting as the `TileMap` widget, so
+/// it works without a window.
+pub fn render_map(
+    map: &Map,
+    camera: &Camera,
+    image: Option<&SharedImage>,
+    images: &TileImages,
+    elapsed: f64,
+) -> RenderTarget {
+    let mut render_target = RenderTarget::new(
+        camera.width().max(0.0).ceil() as u32,
+        camera.height().max(0.0).ceil() as u32,
+    );
+
+    TileMapRenderObject::draw_image_layers(&mut render_target, map, camera, images);
+
+    // the layers are blended from bottom to top
+    for (l, layer) in map.layers.iter().enumerate() {
+        if !layer.visible || layer.opacity <= 0.0 {
+            continue;
+        }
+
+        TileMapRenderObject::draw_layer(&mut render_target, map, l, camera, image, images, elapsed);
+    }
+
+    render_target
+}
+
+/// Converts the premultiplied ARGB pixels of the render target to straight RGBA bytes.
+pub fn to_rgba(render_target: &RenderTarget) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(render_target.data().len() * 4);
+
+    for pixel in render_target.data() {
+        let a = (pixel >> 24) as u8;
+        let channel = |shift: u32| {
+            let c = (pixel >> shift) & 0xFF;
+
+            match a {
+                0 => 0,
+                255 => c as u8,
+                _ => ((c * 255 + a as u32 / 2) / a as u32).min(255) as u8,
+            }
+        };
+
+        rgba.extend_from_slice(&[channel(16), channel(8), channel(0), a]);
+    }
+
+    rgba
+}
+
+/// Writes the render target as RGBA PNG file.
+pub fn save_png<P: AsRef<Path>>(render_target: &RenderTarget, path: P) -> Result<(), ImageError> {
+    let path = path.as_ref();
+    let file = File::create(path).map_err(|e| io_error(path, e))?;
+
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        render_target.width() as u32,
+        render_target.height() as u32,
+    );
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&to_rgba(render_target)))
+        .map_err(|e| ImageError::Encode(e.to_string()))
+}
+
+/// Reads a PNG file with 8 bits per channel. Returns the width, the height and the straight RGBA
+/// bytes of the image.
+pub fn load_png<P: AsRef<Path>>(path: P) -> Result<(u32, u32, Vec<u8>), ImageError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| io_error(path, e))?;
+
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND);
+
+    let decode_error = |e: png::DecodingError| ImageError::Decode(e.to_string());
+    let (info, mut reader) = decoder.read_info().map_err(decode_error)?;
+
+    if info.bit_depth != png::BitDepth::Eight {
+        return Err(ImageError::Decode(format!(
+            "unsupported bit depth {:?}",
+            info.bit_depth
+        )));
+    }
+
+    let mut data = vec![0; info.buffer_size()];
+    reader.next_frame(&mut data).map_err(decode_error)?;
+
+    let rgba = match info.color_type {
+        png::ColorType::RGBA => data,
+        png::ColorType::RGB => data
+            .chunks(3)
+            .flat_map(|p| vec![p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks(2)
+            .flat_map(|p| vec![p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|&g| vec![g, g, g, 255]).collect(),
+        png::ColorType::Indexed => {
+            return Err(ImageError::Decode("unexpanded palette".to_string()));
+        }
+    };
+
+    Ok((info.width, info.height, rgba))
+}
+
+/// Compares the render target with the reference PNG file. Each channel of each pixel may differ
+/// by the given tolerance.
+pub fn compare_golden<P: AsRef<Path>>(
+    render_target: &RenderTarget,
+    path: P,
+    tolerance: u8,
+) -> Result<(), ImageError> {
+    let (width, height, expected) = load_png(path)?;
+    let actual_size = (render_target.width() as u32, render_target.height() as u32);
+
+    if (width, height) != actual_size {
+        return Err(ImageError::Size {
+            expected: (width, height),
+            actual: actual_size,
+        });
+    }
+
+    let actual = to_rgba(render_target);
+    let mut first = None;
+    let mut count = 0;
+
+    for (i, (e, a)) in expected.chunks(4).zip(actual.chunks(4)).enumerate() {
+        // fully transparent pixels are equal regardless of their color
+        if e[3] == 0 && a[3] == 0 {
+            continue;
+        }
+
+        if e.iter()
+            .zip(a)
+            .any(|(e, a)| (*e as i16 - *a as i16).abs() > tolerance as i16)
+        {
+            first = first.or(Some(i as u32));
+            count += 1;
+        }
+    }
+
+    match first {
+        Some(i) => Err(ImageError::Mismatch {
+            count,
+            x: i % width,
+            y: i / width,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Asserts that the render target matches the reference PNG file, see `compare_golden`.
+///
+/// On a mismatch the render target is written next to the reference with the extension
+/// `actual.png`. If the environment variable `ORBGAME_UPDATE_GOLDEN` is set the reference is
+/// written instead.
+pub fn assert_golden<P: AsRef<Path>>(render_target: &RenderTarget, path: P, tolerance: u8) {
+    let path = path.as_ref();
+
+    if env::var_os(UPDATE_GOLDEN).is_some() {
+        if let Err(error) = save_png(render_target, path) {
+            panic!("{}", error);
+        }
+
+        return;
+    }
+
+    if let Err(error) = compare_golden(render_target, path, tolerance) {
+        let actual = path.with_extension("actual.png");
+        let _ = save_png(render_target, &actual);
+
+        panic!(
+            "{} does not match the golden image: {}, see {}",
+            path.display(),
+            error,
+            actual.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{render::Image, utils::CameraBuilder};
+
+    #[test]
+    fn test_to_rgba() {
+        let mut render_target = RenderTarget::new(3, 1);
+        render_target
+            .data_mut()
+            .copy_from_slice(&[0xFF10_2030, 0x8040_0000, 0]);
+
+        assert_eq!(
+            vec![0x10, 0x20, 0x30, 0xFF, 0x80, 0, 0, 0x80, 0, 0, 0, 0],
+            to_rgba(&render_target)
+        );
+    }
+
+    #[test]
+    fn test_golden() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 2, column_count: 2, tile_size: 2, blocked_tiles: [],
+                layers: [(tiles: [0, 1, 1, 0])],
+            )",
+        )
+        .unwrap();
+        let image = Image::from_data(
+            4,
+            2,
+            vec![0xFF00_00FF, 0xFF00_00FF, 0x8080_0000, 0x8080_0000]
+                .into_iter()
+                .cycle()
+                .take(8)
+                .collect(),
+        )
+        .unwrap();
+        let camera = CameraBuilder::new().width(4.0).height(4.0).build();
+        let render_target = render_map(
+            &map,
+            &camera,
+            Some(&SharedImage::new(image)),
+            &TileImages::default(),
+            0.0,
+        );
+
+        let path = env::temp_dir().join(format!("orbgame_golden_{}.png", std::process::id()));
+        save_png(&render_target, &path).unwrap();
+
+        let (width, height, rgba) = load_png(&path).unwrap();
+        assert_eq!((4, 4), (width, height));
+        assert_eq!(
+            &[0, 0, 0xFF, 0xFF, 0, 0, 0xFF, 0xFF, 0xFF, 0, 0, 0x80],
+            &rgba[..12]
+        );
+        assert_eq!(Ok(()), compare_golden(&render_target, &path, 0));
+        assert_golden(&render_target, &path, 0);
+
+        // one pixel differs
+        let mut changed = RenderTarget::new(4, 4);
+        changed.data_mut().copy_from_slice(render_target.data());
+        changed.data_mut()[5] = 0xFF00_00FA;
+
+        assert_eq!(
+            Err(ImageError::Mismatch {
+                count: 1,
+                x: 1,
+                y: 1
+            }),
+            compare_golden(&changed, &path, 2)
+        );
+        assert_eq!(Ok(()), compare_golden(&changed, &path, 5));
+        assert_eq!(
+            Err(ImageError::Size {
+                expected: (4, 4),
+                actual: (2, 2)
+            }),
+            compare_golden(&RenderTarget::new(2, 2), &path, 0)
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
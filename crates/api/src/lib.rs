@@ -7,5 +7,10 @@ pub use orbtk::api::Application as Game;
 pub use orbtk::api::*;
 pub use orbtk::render;
 
+pub mod headless;
+pub mod mini_map;
 pub mod prelude;
 pub mod render_object;
+pub mod tile_colors;
+pub mod tile_images;
+pub mod tile_map_buffer;
@@ -0,0 +1,474 @@
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use orbtk::prelude::*;
+
+use crate::{
+    render::RenderTarget,
+    tile_colors::TileColors,
+    tile_images::{SharedImage, TileImages},
+    utils::{Camera, Map},
+};
+
+/// The color of the rectangle of the camera view on a minimap.
+pub const VIEWPORT_COLOR: u32 = 0xFFFF_FFFF;
+
+/// Describes a marker of an entity on a minimap, e.g. a player or an enemy.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MapMarker {
+    /// The position of the entity in pixels of the map.
+    pub position: Point,
+
+    /// The red, green and blue color of the marker.
+    pub color: (u8, u8, u8),
+
+    /// The width and the height of the marker in pixels of the minimap.
+    pub size: f64,
+}
+
+impl MapMarker {
+    /// Creates a new marker with a size of 3 pixels.
+    pub fn new(position: Point, color: (u8, u8, u8)) -> Self {
+        MapMarker {
+            position,
+            color,
+            size: 3.0,
+        }
+    }
+}
+
+/// Holds the markers of a minimap.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct MapMarkers {
+    markers: Vec<MapMarker>,
+}
+
+into_property_source!(MapMarkers: Vec<MapMarker>);
+
+impl MapMarkers {
+    /// Creates a new empty list of markers.
+    pub fn new() -> Self {
+        MapMarkers::default()
+    }
+
+    /// Adds a marker.
+    pub fn push(&mut self, marker: MapMarker) {
+        self.markers.push(marker);
+    }
+
+    /// Removes all markers.
+    pub fn clear(&mut self) {
+        self.markers.clear();
+    }
+
+    /// Gets an iterator over the markers.
+    pub fn iter(&self) -> impl Iterator<Item = &MapMarker> {
+        self.markers.iter()
+    }
+
+    /// Gets the number of markers.
+    pub fn len(&self) -> usize {
+        self.markers.len()
+    }
+
+    /// Returns `true` if there are no markers.
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+}
+
+impl From<Vec<MapMarker>> for MapMarkers {
+    fn from(markers: Vec<MapMarker>) -> Self {
+        MapMarkers { markers }
+    }
+}
+
+/// Describes how a map is placed on a minimap. The whole map is scaled to fit the minimap and
+/// centered.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MiniMapLayout {
+    scale: f64,
+    offset: Point,
+}
+
+impl MiniMapLayout {
+    /// Creates the layout of the map on a minimap with the given size.
+    pub fn new(map: &Map, size: (f64, f64)) -> Self {
+        let width = map.column_count() as f64 * map.tile_size() as f64;
+        let height = map.row_count() as f64 * map.tile_size() as f64;
+
+        if width <= 0.0 || height <= 0.0 {
+            return MiniMapLayout {
+                scale: 0.0,
+                offset: Point::default(),
+            };
+        }
+
+        let scale = (size.0 / width).min(size.1 / height);
+
+        MiniMapLayout {
+            scale,
+            offset: Point::new(
+                (size.0 - width * scale) / 2.0,
+                (size.1 - height * scale) / 2.0,
+            ),
+        }
+    }
+
+    /// Gets the size of a pixel of the map in pixels of the minimap.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Gets the position of the map on the minimap.
+    pub fn offset(&self) -> Point {
+        self.offset
+    }
+
+    /// Converts a position in the world to a position on the minimap.
+    pub fn world_to_mini_map(&self, position: Point) -> Point {
+        Point::new(
+            self.offset.x() + position.x() * self.scale,
+            self.offset.y() + position.y() * self.scale,
+        )
+    }
+
+    /// Converts a position on the minimap to a position in the world.
+    pub fn mini_map_to_world(&self, position: Point) -> Point {
+        if self.scale <= 0.0 {
+            return Point::default();
+        }
+
+        Point::new(
+            (position.x() - self.offset.x()) / self.scale,
+            (position.y() - self.offset.y()) / self.scale,
+        )
+    }
+}
+
+// The rendered tiles and the state of the map they were drawn from.
+#[derive(Default)]
+struct Buffer {
+    tiles: RenderTarget,
+    render_target: RenderTarget,
+    valid: bool,
+    size: (u32, u32),
+    stamp: u64,
+    image: Option<SharedImage>,
+    images: TileImages,
+    colors: TileColors,
+    version: u64,
+    bounds: Rectangle,
+}
+
+// Fills the rectangle (x, y, width, height) of the render target with the pixel. The parts outside
+// of the render target are skipped.
+fn fill_rect(render_target: &mut RenderTarget, rect: (i32, i32, i32, i32), pixel: u32) {
+    let width = render_target.width() as i32;
+    let height = render_target.height() as i32;
+    let start_x = rect.0.max(0);
+    let end_x = (rect.0 + rect.2).min(width);
+
+    if start_x >= end_x {
+        return;
+    }
+
+    for y in rect.1.max(0)..(rect.1 + rect.3).min(height) {
+        let row = (y * width) as usize;
+
+        for p in &mut render_target.data_mut()[row + start_x as usize..row + end_x as usize] {
+            *p = pixel;
+        }
+    }
+}
+
+/// Holds a minimap of a tile map that shows each tile as a block of its color, the view of the
+/// camera and markers of entities.
+///
+/// The tiles are drawn again only if a tile of the map, the size, the images or the colors have
+/// changed. All clones share the same buffer.
+#[derive(Clone, Default)]
+pub struct MiniMapBuffer {
+    buffer: Rc<RefCell<Buffer>>,
+}
+
+into_property_source!(MiniMapBuffer);
+
+impl MiniMapBuffer {
+    /// Creates a new empty buffer.
+    pub fn new() -> Self {
+        MiniMapBuffer::default()
+    }
+
+    /// Draws the tiles of the map with the given size if anything has changed since the last
+    /// update. Each pixel shows the color of the topmost visible tile below it. Returns `true` if
+    /// the tiles were redrawn.
+    pub fn update(
+        &self,
+        map: &Map,
+        image: Option<&SharedImage>,
+        images: &TileImages,
+        colors: &TileColors,
+        size: (u32, u32),
+    ) -> bool {
+        let mut buffer = self.buffer.borrow_mut();
+        let buffer = &mut *buffer;
+
+        if buffer.valid
+            && buffer.size == size
+            && buffer.stamp == map.changes.stamp()
+            && buffer.image.as_ref() == image
+            && buffer.images.is_same(images)
+            && buffer.colors == *colors
+            && buffer.version == colors.version()
+        {
+            return false;
+        }
+
+        if buffer.size != size {
+            buffer.tiles = RenderTarget::new(size.0, size.1);
+            buffer.render_target = RenderTarget::new(size.0, size.1);
+            buffer.size = size;
+        }
+
+        let layout = MiniMapLayout::new(map, (size.0 as f64, size.1 as f64));
+        let layers: Vec<usize> = (0..map.layers.len())
+            .rev()
+            .filter(|l| map.layers[*l].visible && map.layers[*l].opacity > 0.0)
+            .collect();
+
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                let position = layout.mini_map_to_world(Point::new(x as f64 + 0.5, y as f64 + 0.5));
+
+                let pixel = map.tile_at(position).and_then(|(column, row)| {
+                    layers.iter().find_map(|l| {
+                        colors.color(map, map.get_tile(*l, row, column), image, images)
+                    })
+                });
+
+                buffer.tiles.data_mut()[(y * size.0 + x) as usize] = pixel.unwrap_or(0);
+            }
+        }
+
+        buffer.valid = true;
+        buffer.stamp = map.changes.stamp();
+        buffer.image = image.cloned();
+        buffer.images = images.clone();
+        buffer.colors = colors.clone();
+        buffer.version = colors.version();
+
+        true
+    }
+
+    /// Draws the tiles with the rectangle of the camera view and the markers on top into the
+    /// render target of the buffer.
+    pub fn draw(&self, map: &Map, camera: &Camera, markers: &MapMarkers) {
+        let mut buffer = self.buffer.borrow_mut();
+        let buffer = &mut *buffer;
+        buffer
+            .render_target
+            .data_mut()
+            .copy_from_slice(buffer.tiles.data());
+
+        let layout = MiniMapLayout::new(map, (buffer.size.0 as f64, buffer.size.1 as f64));
+        let render_target = &mut buffer.render_target;
+
+        // the outline of the view of the camera
+        let top_left = layout.world_to_mini_map(Point::new(camera.x(), camera.y()));
+        let bottom_right = layout.world_to_mini_map(Point::new(
+            camera.x() + camera.view_width(),
+            camera.y() + camera.view_height(),
+        ));
+        let (left, top) = (top_left.x().floor() as i32, top_left.y().floor() as i32);
+        let width = (bottom_right.x().ceil() as i32 - left).max(1);
+        let height = (bottom_right.y().ceil() as i32 - top).max(1);
+
+        fill_rect(render_target, (left, top, width, 1), VIEWPORT_COLOR);
+        fill_rect(
+            render_target,
+            (left, top + height - 1, width, 1),
+            VIEWPORT_COLOR,
+        );
+        fill_rect(render_target, (left, top, 1, height), VIEWPORT_COLOR);
+        fill_rect(
+            render_target,
+            (left + width - 1, top, 1, height),
+            VIEWPORT_COLOR,
+        );
+
+        for marker in markers.iter() {
+            let center = layout.world_to_mini_map(marker.position);
+            let size = marker.size.max(1.0).round() as i32;
+            let pixel = 0xFF00_0000
+                | ((marker.color.0 as u32) << 16)
+                | ((marker.color.1 as u32) << 8)
+                | marker.color.2 as u32;
+
+            fill_rect(
+                render_target,
+                (
+                    (center.x() - size as f64 / 2.0 + 0.5).floor() as i32,
+                    (center.y() - size as f64 / 2.0 + 0.5).floor() as i32,
+                    size,
+                    size,
+                ),
+                pixel,
+            );
+        }
+    }
+
+    /// Marks the buffer as outdated, so the tiles are redrawn on the next update.
+    pub fn invalidate(&self) {
+        self.buffer.borrow_mut().valid = false;
+    }
+
+    /// Gets the render target of the buffer.
+    pub fn render_target(&self) -> Ref<'_, RenderTarget> {
+        Ref::map(self.buffer.borrow(), |b| &b.render_target)
+    }
+
+    /// Sets the bounds of the minimap on the screen, i.e. its absolute position and size.
+    pub fn set_bounds(&self, bounds: Rectangle) {
+        self.buffer.borrow_mut().bounds = bounds;
+    }
+
+    /// Gets the bounds of the minimap on the screen it was last drawn at.
+    pub fn bounds(&self) -> Rectangle {
+        self.buffer.borrow().bounds
+    }
+
+    /// Converts a position on the screen, e.g. of a mouse event, to the position in the world of
+    /// the map. Returns `None` if the minimap was not drawn yet.
+    pub fn screen_to_world(&self, map: &Map, position: Point) -> Option<Point> {
+        let bounds = self.bounds();
+
+        if bounds.width() < 1.0 || bounds.height() < 1.0 {
+            return None;
+        }
+
+        let layout = MiniMapLayout::new(map, (bounds.width(), bounds.height()));
+        Some(layout.mini_map_to_world(Point::new(
+            position.x() - bounds.x(),
+            position.y() - bounds.y(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render::Image, utils::CameraBuilder};
+
+    fn map() -> Map {
+        Map::from_ron_str(
+            "(layer_count: 2, row_count: 2, column_count: 4, tile_size: 8, blocked_tiles: [],
+                layers: [(tiles: [0, 0, 0, 0, 0, 0, 0, 0]), (tiles: [-1, 1, -1, -1, -1, -1, -1, -1])],
+            )",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = MiniMapLayout::new(&map(), (8.0, 8.0));
+
+        // the map of 32 x 16 pixels is scaled by a quarter and centered vertically
+        assert_eq!(0.25, layout.scale());
+        assert_eq!(Point::new(0.0, 2.0), layout.offset());
+        assert_eq!(
+            Point::new(4.0, 4.0),
+            layout.world_to_mini_map(Point::new(16.0, 8.0))
+        );
+        assert_eq!(
+            Point::new(16.0, 8.0),
+            layout.mini_map_to_world(Point::new(4.0, 4.0))
+        );
+    }
+
+    #[test]
+    fn test_screen_to_world() {
+        let map = map();
+        let buffer = MiniMapBuffer::new();
+        assert_eq!(None, buffer.screen_to_world(&map, Point::new(4.0, 4.0)));
+
+        // the minimap is not drawn at the origin of the screen
+        buffer.set_bounds(Rectangle::new(Point::new(100.0, 50.0), 8.0, 8.0));
+        assert_eq!(
+            Some(Point::new(16.0, 8.0)),
+            buffer.screen_to_world(&map, Point::new(104.0, 54.0))
+        );
+        assert_eq!(
+            Some(Point::new(0.0, 0.0)),
+            buffer.screen_to_world(&map, Point::new(100.0, 52.0))
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let mut map = map();
+        let image = SharedImage::new(Image::from_data(16, 8, vec![0xFF00_0010; 128]).unwrap());
+        let images = TileImages::default();
+        let colors = TileColors::new().with_color(1, (0, 0, 0x20));
+        let buffer = MiniMapBuffer::new();
+
+        assert!(buffer.update(&map, Some(&image), &images, &colors, (8, 4)));
+        assert!(!buffer.update(&map, Some(&image), &images, &colors, (8, 4)));
+
+        // each tile is a block of 2 x 2 pixels, the topmost tile is shown
+        let markers = MapMarkers::new();
+        let camera = CameraBuilder::new().x(64.0).build();
+        buffer.draw(&map, &camera, &markers);
+        assert_eq!(
+            &[0x10, 0x10, 0x20, 0x20, 0x10, 0x10, 0x10, 0x10],
+            &buffer.render_target().data()[..8]
+                .iter()
+                .map(|p| p & 0xFF)
+                .collect::<Vec<_>>()[..]
+        );
+
+        // changed tiles and colors are drawn again
+        map.set_tile(1, 1, 0, -1);
+        assert!(buffer.update(&map, Some(&image), &images, &colors, (8, 4)));
+        colors.set_color(0, (0, 0, 0x30));
+        assert!(buffer.update(&map, Some(&image), &images, &colors, (8, 4)));
+        buffer.draw(&map, &camera, &markers);
+        assert_eq!(0xFF00_0030, buffer.render_target().data()[2]);
+    }
+
+    #[test]
+    fn test_draw() {
+        let map = map();
+        let colors = TileColors::new().with_color(0, (0, 0, 1));
+        let buffer = MiniMapBuffer::new();
+        buffer.update(&map, None, &TileImages::default(), &colors, (8, 4));
+
+        // the view of 16 x 12 pixels in the middle of the map and a marker at the top left
+        let camera = CameraBuilder::new()
+            .x(8.0)
+            .y(4.0)
+            .width(16.0)
+            .height(12.0)
+            .build();
+        let markers = MapMarkers::from(vec![MapMarker {
+            size: 1.0,
+            ..MapMarker::new(Point::new(0.0, 0.0), (0xFF, 0, 0))
+        }]);
+        buffer.draw(&map, &camera, &markers);
+
+        let v = VIEWPORT_COLOR;
+        let t = 0xFF00_0001;
+        let m = 0xFFFF_0000;
+        assert_eq!(
+            &[
+                m, t, t, t, t, t, t, t, //
+                t, t, v, v, v, v, t, t, //
+                t, t, v, t, t, v, t, t, //
+                t, t, v, v, v, v, t, t, //
+            ],
+            buffer.render_target().data()
+        );
+    }
+}
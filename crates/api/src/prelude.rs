@@ -1,3 +1,8 @@
 pub use crate::*;
 
+pub use crate::headless::*;
+pub use crate::mini_map::*;
 pub use crate::render_object::*;
+pub use crate::tile_colors::*;
+pub use crate::tile_images::*;
+pub use crate::tile_map_buffer::*;
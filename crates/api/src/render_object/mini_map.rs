@@ -0,0 +1,65 @@
+use crate::{
+    prelude::*,
+    utils::{Camera, Point, Rectangle, SharedMap},
+};
+
+pub struct MiniMapRenderObject;
+
+impl Into<Box<dyn RenderObject>> for MiniMapRenderObject {
+    fn into(self) -> Box<dyn RenderObject> {
+        Box::new(self)
+    }
+}
+
+impl RenderObject for MiniMapRenderObject {
+    fn render_self(&self, context: &mut Context, global_position: &Point) {
+        let (bounds, camera, map, image, images, colors, markers, buffer) = {
+            let widget = context.widget();
+            (
+                widget.clone::<Rectangle>("bounds"),
+                widget.clone::<Camera>("camera"),
+                widget.clone::<SharedMap>("map"),
+                widget.try_clone::<SharedImage>("image"),
+                widget.try_clone::<TileImages>("images").unwrap_or_default(),
+                widget.try_clone::<TileColors>("colors").unwrap_or_default(),
+                widget
+                    .try_clone::<MapMarkers>("markers")
+                    .unwrap_or_default(),
+                widget
+                    .try_clone::<MiniMapBuffer>("buffer")
+                    .unwrap_or_default(),
+            )
+        };
+
+        if bounds.width() < 1.0 || bounds.height() < 1.0 {
+            return;
+        }
+
+        // the mouse events are reported on the screen, so the buffer keeps the absolute bounds
+        let bounds = Rectangle::new(
+            Point::new(
+                global_position.x() + bounds.x(),
+                global_position.y() + bounds.y(),
+            ),
+            bounds.width(),
+            bounds.height(),
+        );
+        buffer.set_bounds(bounds);
+
+        let map = map.borrow();
+        buffer.update(
+            &map,
+            image.as_ref(),
+            &images,
+            &colors,
+            (bounds.width() as u32, bounds.height() as u32),
+        );
+        buffer.draw(&map, &camera, &markers);
+
+        context.render_context_2_d().draw_render_target(
+            &buffer.render_target(),
+            bounds.x(),
+            bounds.y(),
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub use self::mini_map::*;
 pub use self::tile_map::*;
 
+mod mini_map;
 mod tile_map;
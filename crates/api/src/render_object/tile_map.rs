@@ -3,37 +3,477 @@ use std::cmp;
 use crate::{
     prelude::*,
     render::{Image, RenderTarget},
-    utils::{Camera, Map, Point, Rectangle},
+    utils::{Camera, ImageLayer, Layer, Map, Point, Rectangle, SharedMap, Tile},
 };
 
 pub struct TileMapRenderObject;
 
+// Multiplies the channels of a premultiplied argb pixel with the opacity and the tint of a layer.
+fn modulate(pixel: u32, opacity: f32, tint: (u8, u8, u8)) -> u32 {
+    let scale = |channel: u32, factor: f32| ((channel & 0xFF) as f32 * factor).round() as u32;
+
+    let a = scale(pixel >> 24, opacity);
+    let r = scale(pixel >> 16, opacity * tint.0 as f32 / 255.0);
+    let g = scale(pixel >> 8, opacity * tint.1 as f32 / 255.0);
+    let b = scale(pixel, opacity * tint.2 as f32 / 255.0);
+
+    (a << 24) | (r << 16) | (g << 8) | b
+}
+
+// Draws a premultiplied argb pixel over another one (source-over).
+pub(crate) fn blend(source: u32, destination: u32) -> u32 {
+    let alpha = source >> 24;
+
+    if alpha == 255 {
+        return source;
+    }
+
+    let inverse = 255 - alpha;
+    let channel = |shift: u32| {
+        let s = (source >> shift) & 0xFF;
+        let d = (destination >> shift) & 0xFF;
+
+        cmp::min(255, s + (d * inverse + 127) / 255) << shift
+    };
+
+    channel(24) | channel(16) | channel(8) | channel(0)
+}
+
+// Linearly interpolates each channel between two premultiplied argb pixels.
+fn mix(a: u32, b: u32, t: f64) -> u32 {
+    let channel = |shift: u32| {
+        let a = ((a >> shift) & 0xFF) as f64;
+        let b = ((b >> shift) & 0xFF) as f64;
+
+        ((a + (b - a) * t).round() as u32) << shift
+    };
+
+    channel(24) | channel(16) | channel(8) | channel(0)
+}
+
+// The image, the clip and the opacity of the clip of a tile.
+pub(crate) struct TileSource<'a> {
+    pub(crate) image: &'a Image,
+    pub(crate) clip: Rectangle,
+    pub(crate) opacity: Opacity,
+}
+
+// Reads the pixels of the clip of a flipped tile.
+struct Sampler<'a> {
+    data: &'a [u32],
+    tile: Tile,
+    clip_x: u32,
+    clip_y: u32,
+    width: u32,
+    height: u32,
+    image_width: u32,
+    image_height: u32,
+}
+
+impl<'a> Sampler<'a> {
+    fn new(source: &TileSource<'a>, tile: Tile) -> Self {
+        Sampler {
+            data: source.image.data(),
+            tile,
+            clip_x: source.clip.x() as u32,
+            clip_y: source.clip.y() as u32,
+            width: source.clip.width() as u32,
+            height: source.clip.height() as u32,
+            image_width: source.image.width() as u32,
+            image_height: source.image.height() as u32,
+        }
+    }
+
+    // Gets the pixel at the given position of the flipped tile.
+    #[inline]
+    fn pixel(&self, x: u32, y: u32) -> Option<u32> {
+        // flipped tiles read the pixels of the tile image in flipped order
+        let (source_x, source_y) = self.tile.source_position(x, y, self.width, self.height);
+        let source_x = self.clip_x + source_x;
+        let source_y = self.clip_y + source_y;
+
+        if source_x >= self.image_width || source_y >= self.image_height {
+            return None;
+        }
+
+        self.data
+            .get((source_y * self.image_width + source_x) as usize)
+            .copied()
+    }
+
+    // Gets the bilinear filtered pixel at the given position of the flipped tile. Samples outside
+    // of the tile are clamped to its border.
+    fn filtered_pixel(&self, x: f64, y: f64) -> Option<u32> {
+        let max_x = self.width.saturating_sub(1) as f64;
+        let max_y = self.height.saturating_sub(1) as f64;
+        let x = x.max(0.0).min(max_x);
+        let y = y.max(0.0).min(max_y);
+        let (left, top) = (x.floor(), y.floor());
+        let (right, bottom) = ((left + 1.0).min(max_x), (top + 1.0).min(max_y));
+
+        let top_pixel = mix(
+            self.pixel(left as u32, top as u32)?,
+            self.pixel(right as u32, top as u32)?,
+            x - left,
+        );
+        let bottom_pixel = mix(
+            self.pixel(left as u32, bottom as u32)?,
+            self.pixel(right as u32, bottom as u32)?,
+            x - left,
+        );
+
+        Some(mix(top_pixel, bottom_pixel, y - top))
+    }
+}
+
+// The area of the render target a tile is drawn to. If the size differs from the size of the
+// tile, the tile is scaled with the nearest pixel or filtered.
+struct TileDestination {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    filtered: bool,
+}
+
+// Gets the screen position of the edge of a tile at the given column or row.
+pub(crate) fn tile_edge(index: i32, tile_size: u32, zoom: f64) -> i32 {
+    (index as f64 * (tile_size as f64 * zoom)).round() as i32
+}
+
 impl TileMapRenderObject {
+    // Draws the clip of the image over the destination of the render target. The parts of the
+    // tile outside of the render target or outside of the image are skipped.
     fn draw_render_target(
-        &self,
         render_target: &mut RenderTarget,
-        image: &Image,
-        clip: Rectangle,
-        x: f64,
-        y: f64,
+        source: &TileSource,
+        layer: &Layer,
+        tile: Tile,
+        destination: &TileDestination,
     ) {
-        let mut y = y as i32;
-        let stride = image.width();
-        let mut offset = clip.y().mul_add(stride, clip.x()) as usize;
-        let last_offset = cmp::min(
-            ((clip.y() + clip.height()).mul_add(stride, clip.x())) as usize,
-            image.data().len(),
+        if source.opacity == Opacity::Transparent {
+            return;
+        }
+
+        let modulated = layer.opacity < 1.0 || !layer.is_untinted();
+
+        // opaque tiles replace the pixels below them
+        let opaque = source.opacity == Opacity::Opaque && layer.opacity >= 1.0;
+
+        let target_width = render_target.width() as i32;
+        let target_height = render_target.height() as i32;
+        let width = source.clip.width() as i32;
+        let height = source.clip.height() as i32;
+        let (x, y) = (destination.x, destination.y);
+
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        // the part of the tile that is inside of the render target
+        let start_i = cmp::max(0, -x);
+        let end_i = cmp::min(destination.width, target_width - x);
+        let start_j = cmp::max(0, -y);
+        let end_j = cmp::min(destination.height, target_height - y);
+
+        let scaled = destination.width != width || destination.height != height;
+        let sampler = Sampler::new(source, tile);
+
+        // the size of a pixel of the destination in pixels of the tile
+        let scale_x = width as f64 / destination.width as f64;
+        let scale_y = height as f64 / destination.height as f64;
+
+        // unscaled and unflipped opaque rows are copied
+        if !scaled && !tile.is_flipped() && opaque && !modulated {
+            let end_i = cmp::min(end_i, sampler.image_width as i32 - sampler.clip_x as i32);
+            let end_j = cmp::min(end_j, sampler.image_height as i32 - sampler.clip_y as i32);
+
+            if start_i >= end_i {
+                return;
+            }
+
+            for j in start_j..end_j {
+                let source = ((sampler.clip_y as i32 + j) * sampler.image_width as i32
+                    + sampler.clip_x as i32
+                    + start_i) as usize;
+                let target = ((y + j) * target_width + x + start_i) as usize;
+                let len = (end_i - start_i) as usize;
+
+                render_target.data_mut()[target..target + len]
+                    .copy_from_slice(&sampler.data[source..source + len]);
+            }
+
+            return;
+        }
+
+        for j in start_j..end_j {
+            let row = ((y + j) * target_width) as usize;
+
+            for i in start_i..end_i {
+                let pixel = if !scaled {
+                    sampler.pixel(i as u32, j as u32)
+                } else if destination.filtered {
+                    sampler.filtered_pixel(
+                        (i as f64 + 0.5) * scale_x - 0.5,
+                        (j as f64 + 0.5) * scale_y - 0.5,
+                    )
+                } else {
+                    sampler.pixel(
+                        (i * width / destination.width) as u32,
+                        (j * height / destination.height) as u32,
+                    )
+                };
+
+                let pixel = match pixel {
+                    Some(pixel) => pixel,
+                    None => continue,
+                };
+
+                let pixel = if modulated {
+                    modulate(pixel, layer.opacity, layer.tint)
+                } else {
+                    pixel
+                };
+                let index = row + (x + i) as usize;
+
+                render_target.data_mut()[index] = if opaque {
+                    pixel
+                } else {
+                    blend(pixel, render_target.data()[index])
+                };
+            }
+        }
+    }
+
+    // Gets the source of the given tile. Each tile set has its own image, the image property is
+    // used for tile sets without an image and for maps without tile sets.
+    pub(crate) fn tile_source<'a>(
+        map: &Map,
+        tile: i32,
+        shared: Option<&'a SharedImage>,
+        images: &'a TileImages,
+    ) -> Option<TileSource<'a>> {
+        let (index, image, position, tile_width, tile_height) = match map.tile_set_index(tile) {
+            Some((index, id)) => {
+                let tile_set = &map.tile_sets[index];
+                let (index, image) = match images.get(index) {
+                    Some(image) => (Some(index), image),
+                    None => (None, shared?.image()),
+                };
+                let position = tile_set.tile_position(id, image.width() as u32)?;
+
+                (
+                    index,
+                    image,
+                    position,
+                    tile_set.tile_width,
+                    tile_set.tile_height,
+                )
+            }
+            None => {
+                let image = shared?.image();
+
+                // without a tile set the image is a tight sheet of square tiles
+                let tile_column_count = image.width() as u32 / map.tile_size;
+
+                if tile_column_count == 0 {
+                    return None;
+                }
+
+                (
+                    None,
+                    image,
+                    (
+                        (tile as u32 % tile_column_count) * map.tile_size,
+                        (tile as u32 / tile_column_count) * map.tile_size,
+                    ),
+                    map.tile_size,
+                    map.tile_size,
+                )
+            }
+        };
+
+        let clip = Rectangle::new(
+            Point::new(position.0 as f64, position.1 as f64),
+            tile_width as f64,
+            tile_height as f64,
         );
 
-        while offset < last_offset {
-            let next_offset = offset + stride as usize;
+        Some(TileSource {
+            image,
+            clip,
+            opacity: match index {
+                Some(index) => images.opacity(index, clip),
+                None => images.shared_opacity(shared?, clip),
+            },
+        })
+    }
+
+    // Draws the tiles of the layer with the given index that are visible by the camera to the
+    // render target. The render position of the camera is moved by the parallax factor of the
+    // layer and floored to whole pixels. Tiles are scaled with the nearest pixel for integer zoom
+    // and filtered for fractional zoom.
+    pub(crate) fn draw_layer(
+        render_target: &mut RenderTarget,
+        map: &Map,
+        l: usize,
+        camera: &Camera,
+        image: Option<&SharedImage>,
+        images: &TileImages,
+        elapsed: f64,
+    ) {
+        let layer = match map.layers.get(l) {
+            Some(layer) => layer,
+            None => return,
+        };
+
+        if map.tile_size == 0 {
+            return;
+        }
+
+        let zoom = camera.zoom();
+        let scaled_size = map.tile_size as f64 * zoom;
+        let render_position = camera.parallax_position(layer.parallax);
+        let origin_x = render_position.x().floor() as i32;
+        let origin_y = render_position.y().floor() as i32;
+        let filtered = zoom.fract() != 0.0;
+
+        // the range of tiles that is covered by the camera, the rounded edges of fractional zoom
+        // need a margin. Larger tiles of tile sets reach into the range from the left and from
+        // below.
+        let margin = if filtered { 1 } else { 0 };
+        let (overhang_columns, overhang_rows) = map.tile_overhang();
+        let start_column = cmp::max(
+            0,
+            (origin_x as f64 / scaled_size).floor() as i32 - margin - overhang_columns as i32,
+        );
+        let start_row = cmp::max(0, (origin_y as f64 / scaled_size).floor() as i32 - margin);
+        let end_column = cmp::min(
+            map.column_count() as i32,
+            ((origin_x as f64 + camera.width().ceil()) / scaled_size).ceil() as i32 + margin,
+        );
+        let end_row = cmp::min(
+            map.row_count() as i32,
+            ((origin_y as f64 + camera.height().ceil()) / scaled_size).ceil() as i32
+                + margin
+                + overhang_rows as i32,
+        );
+
+        for r in start_row..end_row {
+            for c in start_column..end_column {
+                let mut tile = map.get_tile_with_flags(l, r as usize, c as usize);
+
+                if tile.is_empty() {
+                    continue;
+                }
+
+                tile.id = map.animated_tile(tile.id, elapsed);
+
+                if let Some(source) = TileMapRenderObject::tile_source(map, tile.id, image, images)
+                {
+                    // tiles of tile sets can be larger than the tiles of the map, they are
+                    // anchored at the bottom left of their tile like in Tiled
+                    let x = tile_edge(c, map.tile_size, zoom);
+                    let bottom = tile_edge(r + 1, map.tile_size, zoom);
+                    let right = ((c as f64 * map.tile_size as f64 + source.clip.width()) * zoom)
+                        .round() as i32;
+                    let y = (((r + 1) as f64 * map.tile_size as f64 - source.clip.height()) * zoom)
+                        .round() as i32;
+
+                    TileMapRenderObject::draw_render_target(
+                        render_target,
+                        &source,
+                        layer,
+                        tile,
+                        &TileDestination {
+                            x: x - origin_x,
+                            y: y - origin_y,
+                            width: right - x,
+                            height: bottom - y,
+                            filtered,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    // Draws the image of an image layer to the render target. The image is placed at the offset
+    // of the layer, moved by the parallax factor of the layer and repeated to cover the render
+    // target in the directions the layer is repeated in.
+    pub(crate) fn draw_image_layer(
+        render_target: &mut RenderTarget,
+        layer: &ImageLayer,
+        image: &Image,
+        camera: &Camera,
+    ) {
+        if !layer.visible || layer.opacity <= 0.0 || image.width() == 0.0 || image.height() == 0.0 {
+            return;
+        }
+
+        let zoom = camera.zoom();
+        let render_position = camera.parallax_position(layer.parallax);
+        let x = (layer.offset.0 * zoom).round() as i32 - render_position.x().floor() as i32;
+        let y = (layer.offset.1 * zoom).round() as i32 - render_position.y().floor() as i32;
+        let width = (image.width() * zoom).round() as i32;
+        let height = (image.height() * zoom).round() as i32;
+
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        // the first and the last position of the image that covers the render target
+        let range = |position: i32, size: i32, target_size: i32, repeat: bool| {
+            if repeat {
+                let first = position.rem_euclid(size);
+                (if first > 0 { first - size } else { 0 }, target_size)
+            } else {
+                (position, position + 1)
+            }
+        };
+        let (start_x, end_x) = range(x, width, render_target.width() as i32, layer.repeat_x);
+        let (start_y, end_y) = range(y, height, render_target.height() as i32, layer.repeat_y);
+
+        let source = TileSource {
+            image,
+            clip: Rectangle::new(Point::new(0.0, 0.0), image.width(), image.height()),
+            opacity: Opacity::Translucent,
+        };
+        let tinted = Layer {
+            opacity: layer.opacity,
+            ..Layer::default()
+        };
+
+        for y in (start_y..end_y).step_by(height as usize) {
+            for x in (start_x..end_x).step_by(width as usize) {
+                TileMapRenderObject::draw_render_target(
+                    render_target,
+                    &source,
+                    &tinted,
+                    Tile::new(0),
+                    &TileDestination {
+                        x,
+                        y,
+                        width,
+                        height,
+                        filtered: zoom.fract() != 0.0,
+                    },
+                );
+            }
+        }
+    }
 
-            for i in 0..clip.width() as usize {
-                let index = (x as f64 + y as f64 * render_target.width()).floor() as usize + i;
-                render_target.data_mut()[index] = image.data()[offset + i];
+    // Draws the visible image layers of the map with their images to the render target.
+    pub(crate) fn draw_image_layers(
+        render_target: &mut RenderTarget,
+        map: &Map,
+        camera: &Camera,
+        images: &TileImages,
+    ) {
+        for (i, layer) in map.image_layers.iter().enumerate() {
+            if let Some(image) = images.layer_image(i) {
+                TileMapRenderObject::draw_image_layer(render_target, layer, image, camera);
             }
-            offset = next_offset;
-            y += 1;
         }
     }
 }
@@ -45,14 +485,21 @@ impl Into<Box<dyn RenderObject>> for TileMapRenderObject {
 }
 
 impl RenderObject for TileMapRenderObject {
-    fn render_self(&self, context: &mut Context, _: &Point) {
-        let (bounds, camera, map, mut image) = {
+    fn render_self(&self, context: &mut Context, global_position: &Point) {
+        // the map, the images and the buffer are shared, so the clones do not copy tiles or
+        // pixels
+        let (bounds, camera, map, image, images, elapsed, buffer) = {
             let widget = context.widget();
             (
                 widget.clone::<Rectangle>("bounds"),
                 widget.clone::<Camera>("camera"),
-                widget.clone::<Map>("map"),
-                widget.try_clone::<Image>("image"),
+                widget.clone::<SharedMap>("map"),
+                widget.try_clone::<SharedImage>("image"),
+                widget.try_clone::<TileImages>("images").unwrap_or_default(),
+                widget.try_clone::<f64>("elapsed").unwrap_or_default(),
+                widget
+                    .try_clone::<TileMapBuffer>("buffer")
+                    .unwrap_or_default(),
             )
         };
 
@@ -60,74 +507,503 @@ impl RenderObject for TileMapRenderObject {
             return;
         }
 
-        if let Some(image) = &mut image {
-            // draw the tile map
-            let tile_size = map.tile_size;
+        if image.is_none() && images.is_empty() {
+            return;
+        }
+
+        // the mouse events are reported on the screen, so the buffer keeps the absolute bounds
+        let bounds = Rectangle::new(
+            Point::new(
+                global_position.x() + bounds.x(),
+                global_position.y() + bounds.y(),
+            ),
+            bounds.width(),
+            bounds.height(),
+        );
+        buffer.set_bounds(bounds);
+
+        buffer.update(
+            &map.borrow(),
+            &camera,
+            image.as_ref(),
+            &images,
+            elapsed,
+            (bounds.width() as u32, bounds.height() as u32),
+        );
+
+        context.render_context_2_d().draw_render_target(
+            &buffer.render_target(),
+            bounds.x(),
+            bounds.y(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::CameraBuilder;
+
+    const TILE_SIZE: u32 = 4;
+
+    // 4 x 3 tiles with empty tiles and flipped tiles
+    fn map() -> Map {
+        let mut map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 3, column_count: 4, tile_size: 4, blocked_tiles: [],
+                layers: [(tiles: [0, 1, 2, 3, 3, -1, 1, 0, 2, 0, -1, 1])],
+            )",
+        )
+        .unwrap();
+        map.set_tile_with_flags(0, 1, 2, Tile::new(3).flipped_x());
+        map.set_tile_with_flags(0, 3, 1, Tile::new(2).rotated());
+        map
+    }
+
+    // a sheet of 2 x 2 tiles, each pixel has an unique value
+    fn image() -> Image {
+        let size = 2 * TILE_SIZE;
+        Image::from_data(
+            size,
+            size,
+            (0..size * size).map(|i| 0xFF00_0000 | (i + 1)).collect(),
+        )
+        .unwrap()
+    }
+
+    // Gets the expected pixel at the given world position.
+    fn expected(map: &Map, image: &Image, x: i32, y: i32) -> u32 {
+        let size = TILE_SIZE as i32;
+
+        if x < 0
+            || y < 0
+            || x >= map.column_count() as i32 * size
+            || y >= map.row_count() as i32 * size
+        {
+            return 0;
+        }
+
+        let tile = map.get_tile_with_flags(0, (y / size) as usize, (x / size) as usize);
 
-            let start_column = (camera.x() as f32 / tile_size as f32).floor() as usize;
-            let end_column =
-                start_column + (camera.width() as f32 / tile_size as f32).ceil() as usize;
-            let start_row = (camera.y() as f32 / tile_size as f32).floor() as usize;
-            let end_row = start_row + (camera.height() as f32 / tile_size as f32).ceil() as usize;
-            let offset_x = -camera.x() as f32 + start_column as f32 * tile_size as f32;
-            let offset_y = -camera.y() as f32 + start_row as f32 * tile_size as f32;
+        if tile.is_empty() {
+            return 0;
+        }
 
-            for l in 0..map.layer_count {
-                let mut render_target =
-                    RenderTarget::new(bounds.width() as u32, bounds.height() as u32);
+        let (local_x, local_y) =
+            tile.source_position((x % size) as u32, (y % size) as u32, TILE_SIZE, TILE_SIZE);
+        let source_x = (tile.id as u32 % 2) * TILE_SIZE + local_x;
+        let source_y = (tile.id as u32 / 2) * TILE_SIZE + local_y;
 
-                // add 1 to prevent missing tiles at the borders
-                let mut end_column = end_column + 1;
-                let mut end_row = end_row + 1;
+        image.data()[(source_y * image.width() as u32 + source_x) as usize]
+    }
 
-                if end_column > map.column_count() {
-                    end_column = map.column_count();
-                }
+    #[test]
+    fn test_draw_layer_clipping() {
+        let map = map();
+        let image = image();
+        let shared = SharedImage::new(image.clone());
+        let images = TileImages::default();
+        let (width, height) = (7, 5);
 
-                if end_row > map.row_count() {
-                    end_row = map.row_count();
-                }
+        for camera_y in -12..28 {
+            for camera_x in -12..36 {
+                // whole and fractional camera positions
+                for fraction in &[0.0, 0.25, 0.5, 0.75] {
+                    let camera = CameraBuilder::new()
+                        .x(camera_x as f64 / 2.0 + fraction)
+                        .y(camera_y as f64 / 2.0 + fraction)
+                        .width(width as f64)
+                        .height(height as f64)
+                        .build();
 
-                for r in start_row..end_row {
-                    for c in start_column..end_column {
-                        let tile = map.get_tile(l, r, c);
+                    let mut render_target = RenderTarget::new(width, height);
+                    TileMapRenderObject::draw_layer(
+                        &mut render_target,
+                        &map,
+                        0,
+                        &camera,
+                        Some(&shared),
+                        &images,
+                        0.0,
+                    );
 
-                        if tile == -1 {
-                            continue;
+                    for y in 0..height as i32 {
+                        for x in 0..width as i32 {
+                            assert_eq!(
+                                expected(
+                                    &map,
+                                    &image,
+                                    x + camera.x().floor() as i32,
+                                    y + camera.y().floor() as i32
+                                ),
+                                render_target.data()[(y * width as i32 + x) as usize],
+                                "pixel {}, {} at camera {}, {}",
+                                x,
+                                y,
+                                camera.x(),
+                                camera.y()
+                            );
                         }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw_layer_zoom() {
+        let map = map();
+        let image = image();
+        let shared = SharedImage::new(image.clone());
+        let images = TileImages::default();
+        let (width, height) = (9, 7);
+
+        // integer zoom repeats the nearest pixel
+        for &zoom in &[2, 3] {
+            for camera_y in -6..16 {
+                for camera_x in -6..20 {
+                    let camera = CameraBuilder::new()
+                        .x(camera_x as f64 * 0.75)
+                        .y(camera_y as f64 * 0.75)
+                        .width(width as f64)
+                        .height(height as f64)
+                        .zoom(zoom as f64)
+                        .build();
+                    let origin_x = (camera.x() * zoom as f64).floor() as i32;
+                    let origin_y = (camera.y() * zoom as f64).floor() as i32;
 
-                        let tile_column_count = image.width() as f32 / map.tile_size as f32;
-                        let tile_c = tile as f32 % tile_column_count as f32;
-                        let tile_r = (tile as f32 / tile_column_count as f32).floor();
-
-                        let s_x = (((c - start_column) as f32) * map.tile_size as f32
-                            + offset_x as f32) as i32;
-                        let s_y = (((r - start_row) as f32) * map.tile_size as f32
-                            + offset_y as f32) as i32;
-
-                        self.draw_render_target(
-                            &mut render_target,
-                            image,
-                            Rectangle::new(
-                                Point::new(
-                                    tile_c as f64 * map.tile_size() as f64,
-                                    tile_r as f64 * map.tile_size() as f64,
+                    let mut render_target = RenderTarget::new(width, height);
+                    TileMapRenderObject::draw_layer(
+                        &mut render_target,
+                        &map,
+                        0,
+                        &camera,
+                        Some(&shared),
+                        &images,
+                        0.0,
+                    );
+
+                    for y in 0..height as i32 {
+                        for x in 0..width as i32 {
+                            assert_eq!(
+                                expected(
+                                    &map,
+                                    &image,
+                                    (x + origin_x).div_euclid(zoom),
+                                    (y + origin_y).div_euclid(zoom)
                                 ),
-                                map.tile_size as f64,
-                                map.tile_size as f64,
-                            ),
-                            s_x as f64,
-                            s_y as f64,
-                        );
+                                render_target.data()[(y * width as i32 + x) as usize],
+                                "pixel {}, {} at camera {}, {} with zoom {}",
+                                x,
+                                y,
+                                camera.x(),
+                                camera.y(),
+                                zoom
+                            );
+                        }
                     }
                 }
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw_layer_filtered() {
+        let image = Image::from_data(
+            2,
+            2,
+            vec![0xFF00_0000, 0xFF00_00FE, 0xFF00_0000, 0xFF00_00FE],
+        )
+        .unwrap();
+        let shared = SharedImage::new(image);
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 3, column_count: 3, tile_size: 2, blocked_tiles: [],
+                layers: [(tiles: [0, 0, 0, 0, 0, 0, 0, 0, 0])],
+            )",
+        )
+        .unwrap();
+        let images = TileImages::default();
+
+        // fractional zoom interpolates between the pixels of a tile
+        let camera = CameraBuilder::new()
+            .width(3.0)
+            .height(1.0)
+            .zoom(1.5)
+            .build();
+        let mut render_target = RenderTarget::new(3, 1);
+        TileMapRenderObject::draw_layer(
+            &mut render_target,
+            &map,
+            0,
+            &camera,
+            Some(&shared),
+            &images,
+            0.0,
+        );
+        assert_eq!(
+            &[0xFF00_0000, 0xFF00_007F, 0xFF00_00FE],
+            render_target.data()
+        );
+
+        // the scaled tiles leave no gaps
+        for &zoom in &[0.3, 0.75, 1.25, 1.7] {
+            let camera = CameraBuilder::new()
+                .x(0.4)
+                .width(20.0)
+                .height(20.0)
+                .zoom(zoom)
+                .build();
+            let mut render_target = RenderTarget::new(20, 20);
+            TileMapRenderObject::draw_layer(
+                &mut render_target,
+                &map,
+                0,
+                &camera,
+                Some(&shared),
+                &images,
+                0.0,
+            );
+
+            let covered = ((6.0 - camera.x()) * zoom).floor() as usize;
+            let rows = (6.0 * zoom).floor() as usize;
+
+            for y in 0..rows {
+                for x in 0..covered {
+                    assert_ne!(0, render_target.data()[y * 20 + x] >> 24);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw_layer_parallax() {
+        let mut map = map();
+        map.layers[0].parallax = (0.5, 0.0);
+        let image = image();
+        let shared = SharedImage::new(image.clone());
+        let images = TileImages::default();
+        let camera = CameraBuilder::new()
+            .x(6.0)
+            .y(5.0)
+            .width(7.0)
+            .height(5.0)
+            .build();
 
-                context.render_context_2_d().draw_render_target(
-                    &render_target,
-                    bounds.x(),
-                    bounds.y(),
+        let mut render_target = RenderTarget::new(7, 5);
+        TileMapRenderObject::draw_layer(
+            &mut render_target,
+            &map,
+            0,
+            &camera,
+            Some(&shared),
+            &images,
+            0.0,
+        );
+
+        // the layer is moved by half of the camera position in x and stays in y
+        for y in 0..5 {
+            for x in 0..7 {
+                assert_eq!(
+                    expected(&map, &image, x + 3, y),
+                    render_target.data()[(y * 7 + x) as usize]
                 );
             }
         }
     }
+
+    #[test]
+    fn test_draw_layer_large_tiles() {
+        // a tile of 4 x 4 pixels on a map of 2 x 2 pixel tiles in the bottom left corner
+        let image =
+            Image::from_data(4, 4, (0..16).map(|i| 0xFF00_0000 | (i + 1)).collect()).unwrap();
+        let shared = SharedImage::new(image.clone());
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 3, column_count: 3, tile_size: 2, blocked_tiles: [],
+                layers: [(tiles: [-1, -1, -1, -1, -1, -1, 0, -1, -1])],
+                tile_sets: [(tile_width: 4, tile_height: 4)],
+            )",
+        )
+        .unwrap();
+        let images = TileImages::default();
+
+        // the tile reaches up and to the right, into the view of a camera that does not cover
+        // its own tile
+        let camera = CameraBuilder::new()
+            .x(2.0)
+            .y(2.0)
+            .width(2.0)
+            .height(2.0)
+            .build();
+        let mut render_target = RenderTarget::new(2, 2);
+        TileMapRenderObject::draw_layer(
+            &mut render_target,
+            &map,
+            0,
+            &camera,
+            Some(&shared),
+            &images,
+            0.0,
+        );
+
+        assert_eq!(
+            &[
+                image.data()[2],
+                image.data()[3],
+                image.data()[6],
+                image.data()[7]
+            ],
+            render_target.data()
+        );
+    }
+
+    #[test]
+    fn test_draw_image_layer() {
+        let image = Image::from_data(2, 1, vec![0xFF00_0001, 0xFF00_0002]).unwrap();
+        let mut layer = ImageLayer {
+            offset: (1.0, 1.0),
+            parallax: (0.5, 1.0),
+            ..ImageLayer::default()
+        };
+        let camera = CameraBuilder::new()
+            .x(4.0)
+            .y(1.0)
+            .width(5.0)
+            .height(2.0)
+            .build();
+
+        // a single image at the offset moved by the parallax
+        let mut render_target = RenderTarget::new(5, 2);
+        TileMapRenderObject::draw_image_layer(&mut render_target, &layer, &image, &camera);
+        assert_eq!(
+            &[0xFF00_0002, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            render_target.data()
+        );
+
+        // repeated horizontally
+        layer.repeat_x = true;
+        let mut render_target = RenderTarget::new(5, 2);
+        TileMapRenderObject::draw_image_layer(&mut render_target, &layer, &image, &camera);
+        assert_eq!(
+            &[
+                0xFF00_0002,
+                0xFF00_0001,
+                0xFF00_0002,
+                0xFF00_0001,
+                0xFF00_0002,
+                0,
+                0,
+                0,
+                0,
+                0
+            ],
+            render_target.data()
+        );
+
+        // repeated in both directions
+        layer.repeat_y = true;
+        let mut render_target = RenderTarget::new(5, 2);
+        TileMapRenderObject::draw_image_layer(&mut render_target, &layer, &image, &camera);
+        assert_eq!(&render_target.data()[..5], &render_target.data()[5..]);
+        assert_eq!(0xFF00_0001, render_target.data()[1]);
+    }
+
+    #[test]
+    fn test_draw_render_target_clipping() {
+        let image = image();
+        let layer = Layer::default();
+        let source = TileSource {
+            image: &image,
+            clip: Rectangle::new(Point::new(4.0, 4.0), 8.0, 8.0),
+            opacity: Opacity::Opaque,
+        };
+
+        // the clip reaches over the image and the tile over the render target on every side
+        for &(x, y) in &[
+            (-6, -6),
+            (-6, 2),
+            (2, -6),
+            (2, 2),
+            (5, 5),
+            (-20, 0),
+            (0, 20),
+        ] {
+            let mut render_target = RenderTarget::new(6, 6);
+            TileMapRenderObject::draw_render_target(
+                &mut render_target,
+                &source,
+                &layer,
+                Tile::new(0),
+                &TileDestination {
+                    x,
+                    y,
+                    width: 8,
+                    height: 8,
+                    filtered: false,
+                },
+            );
+
+            for ty in 0..6 {
+                for tx in 0..6 {
+                    let (sx, sy) = (tx - x + 4, ty - y + 4);
+                    let expected =
+                        if tx >= x && ty >= y && tx < x + 8 && ty < y + 8 && sx < 8 && sy < 8 {
+                            image.data()[(sy * 8 + sx) as usize]
+                        } else {
+                            0
+                        };
+
+                    assert_eq!(expected, render_target.data()[(ty * 6 + tx) as usize]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_blend() {
+        assert_eq!(0xFF10_2030, blend(0xFF10_2030, 0xFF80_8080));
+        assert_eq!(0xFF80_8080, blend(0x0000_0000, 0xFF80_8080));
+        assert_eq!(0xFF48_5058, blend(0x8008_1018, 0xFF80_8080));
+        assert_eq!(0x8008_1018, blend(0x8008_1018, 0x0000_0000));
+    }
+
+    #[test]
+    fn test_draw_layers_blended() {
+        // the floor is opaque, the decoration has a transparent and a translucent pixel
+        let image = Image::from_data(
+            2,
+            2,
+            vec![0xFF80_8080, 0xFF80_8080, 0x0000_0000, 0x8008_1018],
+        )
+        .unwrap();
+        let shared = SharedImage::new(image);
+        let map = Map::from_ron_str(
+            "(layer_count: 2, row_count: 1, column_count: 1, tile_size: 2, blocked_tiles: [],
+                layers: [(tiles: [0]), (tiles: [1])],
+                tile_sets: [(tile_width: 2, tile_height: 1)],
+            )",
+        )
+        .unwrap();
+        let camera = CameraBuilder::new().width(2.0).height(2.0).build();
+        let images = TileImages::default();
+
+        // the tiles are anchored at the bottom of the map tile
+        let mut render_target = RenderTarget::new(2, 2);
+
+        for l in 0..2 {
+            TileMapRenderObject::draw_layer(
+                &mut render_target,
+                &map,
+                l,
+                &camera,
+                Some(&shared),
+                &images,
+                0.0,
+            );
+        }
+
+        assert_eq!(&[0, 0, 0xFF80_8080, 0xFF48_5058], render_target.data());
+        assert_eq!(
+            Opacity::Opaque,
+            images.shared_opacity(&shared, Rectangle::new(Point::new(0.0, 0.0), 2.0, 1.0))
+        );
+    }
 }
@@ -0,0 +1,190 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use orbtk::prelude::*;
+
+use crate::{
+    render::Image,
+    render_object::TileMapRenderObject,
+    tile_images::{SharedImage, TileImages},
+    utils::{tile_id, Map},
+};
+
+/// Gets the average color of the pixels of the given clip of the image as opaque premultiplied
+/// argb pixel. The colors are weighted by their alpha. Returns `None` if all pixels are fully
+/// transparent.
+pub fn average_color(image: &Image, clip: Rectangle) -> Option<u32> {
+    let width = image.width() as u32;
+    let height = image.height() as u32;
+    let end_x = ((clip.x() + clip.width()) as u32).min(width);
+    let end_y = ((clip.y() + clip.height()) as u32).min(height);
+    let mut sum = [0u64; 4];
+
+    for y in clip.y() as u32..end_y {
+        for x in clip.x() as u32..end_x {
+            let pixel = match image.data().get((y * width + x) as usize) {
+                Some(pixel) => *pixel,
+                None => continue,
+            };
+
+            for (i, sum) in sum.iter_mut().enumerate() {
+                *sum += ((pixel >> (24 - i * 8)) & 0xFF) as u64;
+            }
+        }
+    }
+
+    if sum[0] == 0 {
+        return None;
+    }
+
+    // the channels are premultiplied, so dividing by the alpha weights them
+    let channel = |c: u64| ((c * 255 + sum[0] / 2) / sum[0]).min(255) as u32;
+
+    Some(0xFF00_0000 | (channel(sum[1]) << 16) | (channel(sum[2]) << 8) | channel(sum[3]))
+}
+
+// The explicit colors and the cached average colors of the images they were worked out from.
+#[derive(Default, Debug)]
+struct Colors {
+    colors: HashMap<i32, u32>,
+    averages: HashMap<i32, Option<u32>>,
+    image: Option<SharedImage>,
+    images: TileImages,
+    version: u64,
+}
+
+/// Holds the colors of tiles, e.g. to draw a tile as a single block of color on a minimap.
+///
+/// Tiles without an explicit color get the average color of their pixels in the image of their
+/// tile set. The average colors are worked out once and cached until other images are used. All
+/// clones share the colors and the cache.
+#[derive(Clone, Default, Debug)]
+pub struct TileColors {
+    colors: Rc<RefCell<Colors>>,
+}
+
+// Tile colors are equal if they share the same colors.
+impl PartialEq for TileColors {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.colors, &other.colors)
+    }
+}
+
+into_property_source!(TileColors);
+
+impl TileColors {
+    /// Creates new tile colors without explicit colors.
+    pub fn new() -> Self {
+        TileColors::default()
+    }
+
+    /// Sets the red, green and blue color of the given tile id and returns the colors.
+    pub fn with_color(self, tile: i32, color: (u8, u8, u8)) -> Self {
+        self.set_color(tile, color);
+        self
+    }
+
+    /// Sets the red, green and blue color of the given tile id. Flip flags of the id are ignored.
+    pub fn set_color(&self, tile: i32, color: (u8, u8, u8)) {
+        let mut colors = self.colors.borrow_mut();
+        let pixel =
+            0xFF00_0000 | ((color.0 as u32) << 16) | ((color.1 as u32) << 8) | color.2 as u32;
+
+        colors.colors.insert(tile_id(tile), pixel);
+        colors.version += 1;
+    }
+
+    /// Removes the explicit color of the given tile id, the average color is used again.
+    pub fn remove_color(&self, tile: i32) {
+        let mut colors = self.colors.borrow_mut();
+
+        if colors.colors.remove(&tile_id(tile)).is_some() {
+            colors.version += 1;
+        }
+    }
+
+    /// Gets the number of changes of the explicit colors.
+    pub fn version(&self) -> u64 {
+        self.colors.borrow().version
+    }
+
+    /// Gets the color of the given tile as opaque premultiplied argb pixel. Returns `None` for
+    /// empty tiles and for tiles without an explicit color that are fully transparent or have no
+    /// image.
+    pub fn color(
+        &self,
+        map: &Map,
+        tile: i32,
+        image: Option<&SharedImage>,
+        images: &TileImages,
+    ) -> Option<u32> {
+        if tile < 0 {
+            return None;
+        }
+
+        let tile = tile_id(tile);
+        let mut colors = self.colors.borrow_mut();
+
+        if let Some(color) = colors.colors.get(&tile) {
+            return Some(*color);
+        }
+
+        if colors.image.as_ref() != image || !colors.images.is_same(images) {
+            colors.averages.clear();
+            colors.image = image.cloned();
+            colors.images = images.clone();
+        }
+
+        *colors.averages.entry(tile).or_insert_with(|| {
+            let source = TileMapRenderObject::tile_source(map, tile, image, images)?;
+
+            average_color(source.image, source.clip)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_color() {
+        let image = Image::from_data(3, 1, vec![0xFF00_00FF, 0x8080_0000, 0x0000_0000]).unwrap();
+        let clip = |x: f64, width: f64| Rectangle::new(Point::new(x, 0.0), width, 1.0);
+
+        assert_eq!(Some(0xFF00_00FF), average_color(&image, clip(0.0, 1.0)));
+        assert_eq!(Some(0xFFFF_0000), average_color(&image, clip(1.0, 1.0)));
+        assert_eq!(None, average_color(&image, clip(2.0, 1.0)));
+
+        // the translucent pixel has less weight
+        assert_eq!(Some(0xFF55_00AA), average_color(&image, clip(0.0, 3.0)));
+    }
+
+    #[test]
+    fn test_tile_colors() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 2, tile_size: 1, blocked_tiles: [],
+                layers: [(tiles: [0, 1])],
+            )",
+        )
+        .unwrap();
+        let image = SharedImage::new(Image::from_data(2, 1, vec![0xFF10_2030, 0]).unwrap());
+        let images = TileImages::default();
+        let colors = TileColors::new().with_color(1, (1, 2, 3));
+
+        assert_eq!(
+            Some(0xFF10_2030),
+            colors.color(&map, 0, Some(&image), &images)
+        );
+        assert_eq!(
+            Some(0xFF01_0203),
+            colors.color(&map, 1, Some(&image), &images)
+        );
+        assert_eq!(None, colors.color(&map, -1, Some(&image), &images));
+        assert_eq!(None, colors.color(&map, 0, None, &images));
+
+        // the transparent tile has no color without an explicit color
+        colors.remove_color(1);
+        assert_eq!(None, colors.color(&map, 1, Some(&image), &images));
+        assert_eq!(2, colors.version());
+    }
+}
@@ -0,0 +1,383 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::Path,
+    rc::{Rc, Weak},
+};
+
+use log::error;
+use orbtk::prelude::*;
+
+use crate::{
+    headless::{io_error, ImageError},
+    render::Image,
+};
+
+/// Describes the alpha of all pixels of a tile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Opacity {
+    /// All pixels are opaque.
+    Opaque,
+
+    /// All pixels are fully transparent.
+    Transparent,
+
+    /// The tile contains semi-transparent pixels or a mix of opaque and transparent pixels.
+    Translucent,
+}
+
+/// Gets the opacity of the given clip of the image. Pixels outside of the image are transparent.
+pub fn tile_opacity(image: &Image, clip: Rectangle) -> Opacity {
+    let width = image.width() as u32;
+    let height = image.height() as u32;
+    let mut opaque = 0;
+    let mut transparent = 0;
+
+    for y in clip.y() as u32..(clip.y() + clip.height()) as u32 {
+        for x in clip.x() as u32..(clip.x() + clip.width()) as u32 {
+            let alpha = if x < width && y < height {
+                image
+                    .data()
+                    .get((y * width + x) as usize)
+                    .map_or(0, |pixel| pixel >> 24)
+            } else {
+                0
+            };
+
+            match alpha {
+                0 => transparent += 1,
+                255 => opaque += 1,
+                _ => return Opacity::Translucent,
+            }
+
+            if opaque > 0 && transparent > 0 {
+                return Opacity::Translucent;
+            }
+        }
+    }
+
+    if opaque > 0 {
+        Opacity::Opaque
+    } else {
+        Opacity::Transparent
+    }
+}
+
+// The image of cached opacities, a tile set image by its index or a shared image by its address.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum ImageKey {
+    TileSet(usize),
+    Shared(usize),
+}
+
+// The image and the clip of a tile.
+type OpacityKey = (ImageKey, u32, u32, u32, u32);
+
+// The cached opacities of tiles.
+#[derive(Default, Debug)]
+struct OpacityCache {
+    opacities: HashMap<OpacityKey, Opacity>,
+
+    // The shared images with cached opacities. The weak references keep the address of an image
+    // from being reused by another image while its opacities are cached.
+    shared: Vec<Weak<Image>>,
+}
+
+/// Holds the images of the tile sets and of the image layers of a map. The image at an index
+/// belongs to the tile set at the same index of `Map::tile_sets`, the layer image at an index to
+/// the image layer at the same index of `Map::image_layers`.
+///
+/// The opacity of each tile is worked out once and cached, also for shared images like the image of
+/// a tile map without tile sets. All clones share the images and the cache, the cache is dropped
+/// with the images when new images are set.
+#[derive(Clone, Default, Debug)]
+pub struct TileImages {
+    images: Rc<Vec<Option<Image>>>,
+    layer_images: Rc<Vec<Option<Image>>>,
+    opacity: Rc<RefCell<OpacityCache>>,
+}
+
+impl PartialEq for TileImages {
+    fn eq(&self, other: &Self) -> bool {
+        self.images == other.images && self.layer_images == other.layer_images
+    }
+}
+
+/// Loads the image from the given path.
+pub fn load_image(path: &str) -> Result<Image, ImageError> {
+    Image::from_path(path).map_err(|e| io_error(Path::new(path), e))
+}
+
+// Loads the images from the given paths.
+fn load_images<S: AsRef<str>>(paths: &[S]) -> Result<Vec<Option<Image>>, ImageError> {
+    paths
+        .iter()
+        .map(|path| load_image(path.as_ref()).map(Some))
+        .collect()
+}
+
+into_property_source!(TileImages: Vec<Image>);
+
+impl TileImages {
+    /// Creates a new list of tile set images.
+    pub fn new(images: Vec<Option<Image>>) -> Self {
+        TileImages {
+            images: Rc::new(images),
+            ..TileImages::default()
+        }
+    }
+
+    /// Loads the images from the given paths. Returns the error of the first image that could
+    /// not be loaded. Use `load_image` and `new` to leave gaps for these images instead.
+    pub fn load<S: AsRef<str>>(paths: &[S]) -> Result<Self, ImageError> {
+        load_images(paths).map(TileImages::new)
+    }
+
+    /// Sets the images of the image layers.
+    pub fn with_layer_images(mut self, layer_images: Vec<Option<Image>>) -> Self {
+        self.layer_images = Rc::new(layer_images);
+        self
+    }
+
+    /// Loads the images of the image layers from the given paths, see `load`.
+    pub fn load_layer_images<S: AsRef<str>>(self, paths: &[S]) -> Result<Self, ImageError> {
+        load_images(paths).map(|layer_images| self.with_layer_images(layer_images))
+    }
+
+    /// Gets the image of the tile set with the given index.
+    pub fn get(&self, index: usize) -> Option<&Image> {
+        self.images.get(index).and_then(|i| i.as_ref())
+    }
+
+    /// Gets the image of the image layer with the given index.
+    pub fn layer_image(&self, index: usize) -> Option<&Image> {
+        self.layer_images.get(index).and_then(|i| i.as_ref())
+    }
+
+    /// Gets the cached opacity of the given clip of the tile set image with the given index. The
+    /// clip is transparent if there is no image at the index.
+    pub fn opacity(&self, index: usize, clip: Rectangle) -> Opacity {
+        match self.get(index) {
+            Some(image) => self.cached_opacity(ImageKey::TileSet(index), image, clip),
+            None => Opacity::Transparent,
+        }
+    }
+
+    /// Gets the cached opacity of the given clip of a shared image, e.g. the image of a tile map
+    /// without tile sets. The opacities of each shared image are cached on their own, so the
+    /// images can be swapped and differ between tile maps that share these images.
+    pub fn shared_opacity(&self, image: &SharedImage, clip: Rectangle) -> Opacity {
+        let address = Rc::as_ptr(&image.image) as usize;
+
+        {
+            let mut cache = self.opacity.borrow_mut();
+
+            if !cache.shared.iter().any(|i| i.as_ptr() as usize == address) {
+                // forgets the opacities of dropped images before their addresses are reused
+                cache.shared.retain(|i| i.strong_count() > 0);
+                let shared: Vec<usize> = cache.shared.iter().map(|i| i.as_ptr() as usize).collect();
+                cache.opacities.retain(|key, _| match key.0 {
+                    ImageKey::Shared(address) => shared.contains(&address),
+                    ImageKey::TileSet(_) => true,
+                });
+                cache.shared.push(Rc::downgrade(&image.image));
+            }
+        }
+
+        self.cached_opacity(ImageKey::Shared(address), image.image(), clip)
+    }
+
+    fn cached_opacity(&self, image_key: ImageKey, image: &Image, clip: Rectangle) -> Opacity {
+        let key = (
+            image_key,
+            clip.x() as u32,
+            clip.y() as u32,
+            clip.width() as u32,
+            clip.height() as u32,
+        );
+
+        *self
+            .opacity
+            .borrow_mut()
+            .opacities
+            .entry(key)
+            .or_insert_with(|| tile_opacity(image, clip))
+    }
+
+    /// Returns `true` if both are clones of the same images.
+    pub fn is_same(&self, other: &TileImages) -> bool {
+        Rc::ptr_eq(&self.images, &other.images)
+            && Rc::ptr_eq(&self.layer_images, &other.layer_images)
+    }
+
+    /// Returns `true` if there are neither tile set images nor layer images.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty() && self.layer_images.is_empty()
+    }
+
+    /// Gets the number of tile set images including gaps.
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+}
+
+impl From<Vec<Image>> for TileImages {
+    fn from(images: Vec<Image>) -> Self {
+        TileImages::new(images.into_iter().map(Some).collect())
+    }
+}
+
+/// Shares an image, e.g. the tile sheet of a tile map, without copying its pixels. All clones
+/// refer to the same image.
+#[derive(Clone, Default, Debug)]
+pub struct SharedImage {
+    image: Rc<Image>,
+}
+
+// Shared images are equal if they share the same image.
+impl PartialEq for SharedImage {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_same(other)
+    }
+}
+
+into_property_source!(SharedImage: Image, &str, String);
+
+impl SharedImage {
+    /// Creates a new shared image that holds the given image.
+    pub fn new(image: Image) -> Self {
+        SharedImage {
+            image: Rc::new(image),
+        }
+    }
+
+    /// Loads the image from the given path.
+    pub fn load(path: &str) -> Result<Self, ImageError> {
+        load_image(path).map(SharedImage::new)
+    }
+
+    /// Gets the image.
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Returns `true` if both share the same image.
+    pub fn is_same(&self, other: &SharedImage) -> bool {
+        Rc::ptr_eq(&self.image, &other.image)
+    }
+}
+
+impl From<Image> for SharedImage {
+    fn from(image: Image) -> Self {
+        SharedImage::new(image)
+    }
+}
+
+// Loads the image from the given path, e.g. for the image property of a tile map. Load errors
+// are logged and an empty image is used, use `SharedImage::load` to handle them.
+impl From<&str> for SharedImage {
+    fn from(path: &str) -> Self {
+        SharedImage::load(path).unwrap_or_else(|e| {
+            error!("could not load image: {}", e);
+            SharedImage::default()
+        })
+    }
+}
+
+impl From<String> for SharedImage {
+    fn from(path: String) -> Self {
+        SharedImage::from(path.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_opacity() {
+        // opaque, transparent and translucent column of a 3 x 2 image
+        let image = Image::from_data(
+            3,
+            2,
+            vec![
+                0xFF10_2030,
+                0x0000_0000,
+                0x8008_1018,
+                0xFF10_2030,
+                0x0000_0000,
+                0xFF10_2030,
+            ],
+        )
+        .unwrap();
+        let column = |x| Rectangle::new(Point::new(x as f64, 0.0), 1.0, 2.0);
+
+        assert_eq!(Opacity::Opaque, tile_opacity(&image, column(0)));
+        assert_eq!(Opacity::Transparent, tile_opacity(&image, column(1)));
+        assert_eq!(Opacity::Translucent, tile_opacity(&image, column(2)));
+        assert_eq!(
+            Opacity::Translucent,
+            tile_opacity(&image, Rectangle::new(Point::new(0.0, 0.0), 2.0, 1.0))
+        );
+
+        // the part outside of the image is transparent
+        assert_eq!(
+            Opacity::Translucent,
+            tile_opacity(&image, Rectangle::new(Point::new(0.0, 1.0), 1.0, 2.0))
+        );
+
+        let images = TileImages::from(vec![image]);
+        let shared = images.clone();
+        assert_eq!(Opacity::Opaque, images.opacity(0, column(0)));
+        assert_eq!(Opacity::Transparent, images.opacity(1, column(0)));
+        assert_eq!(1, shared.opacity.borrow().opacities.len());
+    }
+
+    #[test]
+    fn test_shared_opacity() {
+        let images = TileImages::default();
+        let clip = Rectangle::new(Point::new(0.0, 0.0), 1.0, 1.0);
+        let opaque = SharedImage::new(Image::from_data(1, 1, vec![0xFF10_2030]).unwrap());
+        let transparent = SharedImage::new(Image::from_data(1, 1, vec![0]).unwrap());
+
+        // images of the same size are cached on their own
+        assert_eq!(Opacity::Opaque, images.shared_opacity(&opaque, clip));
+        assert_eq!(
+            Opacity::Transparent,
+            images.shared_opacity(&transparent, clip)
+        );
+        assert_eq!(
+            Opacity::Opaque,
+            images.shared_opacity(&opaque.clone(), clip)
+        );
+        assert_eq!(2, images.opacity.borrow().opacities.len());
+
+        // the opacities of dropped images are forgotten
+        drop(opaque);
+        let translucent = SharedImage::new(Image::from_data(1, 1, vec![0x8008_1018]).unwrap());
+        assert_eq!(
+            Opacity::Translucent,
+            images.shared_opacity(&translucent, clip)
+        );
+        assert_eq!(2, images.opacity.borrow().opacities.len());
+    }
+
+    #[test]
+    fn test_shared_image() {
+        let image = SharedImage::from(Image::from_data(1, 1, vec![0xFF10_2030]).unwrap());
+        let shared = image.clone();
+
+        assert_eq!(image, shared);
+        assert_eq!(&[0xFF10_2030], shared.image().data());
+        assert_ne!(image, SharedImage::new(image.image().clone()));
+
+        assert!(matches!(
+            SharedImage::load("does/not/exist.png"),
+            Err(ImageError::Io { .. })
+        ));
+        assert!(matches!(
+            TileImages::load(&["does/not/exist.png"]),
+            Err(ImageError::Io { .. })
+        ));
+    }
+}
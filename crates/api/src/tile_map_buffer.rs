@@ -0,0 +1,873 @@
+use std::{
+    cell::{Ref, RefCell},
+    cmp,
+    collections::HashMap,
+    rc::Rc,
+};
+
+use orbtk::prelude::*;
+
+use crate::{
+    render::RenderTarget,
+    render_object::{blend, tile_edge, TileMapRenderObject},
+    tile_images::{SharedImage, TileImages},
+    utils::{Camera, CameraBuilder, Map},
+};
+
+/// The default number of tiles of a chunk in each direction.
+pub const CHUNK_SIZE: usize = 16;
+
+// The layers of a map in the order they are drawn. Runs of layers without parallax are
+// pre-rendered into chunks together, layers with parallax are drawn directly.
+enum LayerGroup {
+    Chunked(Vec<usize>),
+    Direct(usize),
+}
+
+// Gets the groups of the visible layers of the map.
+fn layer_groups(map: &Map) -> Vec<LayerGroup> {
+    let mut groups = vec![];
+
+    for (l, layer) in map.layers.iter().enumerate() {
+        if !layer.visible || layer.opacity <= 0.0 {
+            continue;
+        }
+
+        if layer.has_parallax() {
+            groups.push(LayerGroup::Direct(l));
+            continue;
+        }
+
+        match groups.last_mut() {
+            Some(LayerGroup::Chunked(layers)) => layers.push(l),
+            _ => groups.push(LayerGroup::Chunked(vec![l])),
+        }
+    }
+
+    groups
+}
+
+// The pre-rendered layers of a part of the map, one render target for each chunked group of
+// layers.
+struct Chunk {
+    render_targets: Vec<RenderTarget>,
+    elapsed: f64,
+
+    // the position of the first tile of the chunk inside of the render target
+    offset: (i32, i32),
+}
+
+// The render target and the state of the map it was drawn from.
+#[derive(Default)]
+struct Buffer {
+    render_target: RenderTarget,
+    valid: bool,
+    size: (u32, u32),
+    position: (i32, i32),
+    stamp: u64,
+    elapsed: f64,
+    tile_size: u32,
+    zoom: f64,
+    image: Option<SharedImage>,
+    images: TileImages,
+    chunks: HashMap<(usize, usize), Chunk>,
+    bounds: Rectangle,
+}
+
+// Copies the clip (x, y, width, height) of the source to the given position of the target or
+// draws it over the target if blended. The parts outside of the source or the target are skipped.
+fn copy_render_target(
+    target: &mut RenderTarget,
+    source: &RenderTarget,
+    clip: (i32, i32, i32, i32),
+    x: i32,
+    y: i32,
+    blended: bool,
+) {
+    let target_width = target.width() as i32;
+    let target_height = target.height() as i32;
+    let source_width = source.width() as i32;
+    let (clip_x, clip_y) = (clip.0.max(0), clip.1.max(0));
+    let clip_width = cmp::min(clip.2, source_width - clip_x);
+    let clip_height = cmp::min(clip.3, source.height() as i32 - clip_y);
+
+    let start_x = cmp::max(0, -x);
+    let end_x = cmp::min(clip_width, target_width - x);
+
+    if start_x >= end_x {
+        return;
+    }
+
+    for j in cmp::max(0, -y)..cmp::min(clip_height, target_height - y) {
+        let source_index = ((clip_y + j) * source_width + clip_x + start_x) as usize;
+        let target_index = ((y + j) * target_width + x + start_x) as usize;
+        let len = (end_x - start_x) as usize;
+        let source = &source.data()[source_index..source_index + len];
+        let target = &mut target.data_mut()[target_index..target_index + len];
+
+        if blended {
+            for (t, s) in target.iter_mut().zip(source) {
+                *t = blend(*s, *t);
+            }
+        } else {
+            target.copy_from_slice(source);
+        }
+    }
+}
+
+/// Holds all layers of a tile map composited into one render target.
+///
+/// The map is split into chunks of tiles that are pre-rendered and cached. Only the chunks that
+/// intersect the camera are drawn into the render target. A chunk is rendered again if one of
+/// its tiles, or a larger tile of a tile set that reaches into it, is changed by `Map::set_tile`
+/// or if the frame of one of these animated tiles changes.
+/// All chunks are dropped if the map or the images are replaced or if a layer or the tile sets are
+/// changed by the setters of the map, e.g. `Map::set_layer_visible`. All clones share the same
+/// buffer.
+///
+/// Image layers and layers with parallax move independently of the tiles and are not cached, they
+/// are drawn on each redraw between the chunks of the layers below and above them.
+#[derive(Clone)]
+pub struct TileMapBuffer {
+    buffer: Rc<RefCell<Buffer>>,
+    chunk_size: usize,
+}
+
+impl Default for TileMapBuffer {
+    fn default() -> Self {
+        TileMapBuffer::with_chunk_size(CHUNK_SIZE)
+    }
+}
+
+into_property_source!(TileMapBuffer);
+
+impl TileMapBuffer {
+    /// Creates a new empty buffer.
+    pub fn new() -> Self {
+        TileMapBuffer::default()
+    }
+
+    /// Creates a new empty buffer with chunks of the given number of tiles in each direction.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        TileMapBuffer {
+            buffer: Rc::new(RefCell::new(Buffer::default())),
+            chunk_size: cmp::max(1, chunk_size),
+        }
+    }
+
+    /// Marks the buffer and all chunks as outdated, so they are redrawn on the next update.
+    pub fn invalidate(&self) {
+        self.buffer.borrow_mut().valid = false;
+    }
+
+    /// Draws the map through the camera into the render target of the buffer with the given size if
+    /// anything has changed since the last update. Returns `true` if the buffer was redrawn.
+    pub fn update(
+        &self,
+        map: &Map,
+        camera: &Camera,
+        image: Option<&SharedImage>,
+        images: &TileImages,
+        elapsed: f64,
+        size: (u32, u32),
+    ) -> bool {
+        let mut buffer = self.buffer.borrow_mut();
+        let buffer = &mut *buffer;
+        let zoom = camera.zoom();
+        let render_position = camera.render_position();
+        let position = (
+            render_position.x().floor() as i32,
+            render_position.y().floor() as i32,
+        );
+        let mut changed = !buffer.valid || buffer.size != size || buffer.position != position;
+
+        let groups = layer_groups(map);
+        let chunked_count = groups
+            .iter()
+            .filter(|g| matches!(g, LayerGroup::Chunked(_)))
+            .count();
+        let mut camera = camera.clone();
+        camera.set_size(size.0 as f64, size.1 as f64);
+
+        // layers that are drawn directly are redrawn with their animations
+        if !map.image_layers.is_empty() || groups.len() > chunked_count {
+            changed |= map.animation_changed(&camera, buffer.elapsed, elapsed);
+        }
+
+        if !buffer.valid
+            || buffer.tile_size != map.tile_size
+            || buffer.zoom != zoom
+            || !buffer.images.is_same(images)
+            || buffer.image.as_ref() != image
+        {
+            buffer.chunks.clear();
+            buffer.tile_size = map.tile_size;
+            buffer.zoom = zoom;
+            buffer.images = images.clone();
+            buffer.image = image.cloned();
+        }
+
+        // only the chunks with changed tiles are dropped, including the chunks to the right and
+        // above that larger tiles of the tile sets reach into
+        let chunk_size = self.chunk_size;
+        let (overhang_columns, overhang_rows) = map.tile_overhang();
+
+        match map.changes.since(buffer.stamp) {
+            Some(changes) => {
+                for (column, row) in changes {
+                    for y in row.saturating_sub(overhang_rows) / chunk_size..=row / chunk_size {
+                        for x in column / chunk_size..=(column + overhang_columns) / chunk_size {
+                            buffer.chunks.remove(&(x, y));
+                        }
+                    }
+                }
+            }
+            None => buffer.chunks.clear(),
+        }
+
+        buffer.stamp = map.changes.stamp();
+
+        // the screen position of the edge of a tile and the position of the camera of a chunk that
+        // starts at the tile, that is floored like the camera of the buffer
+        let tile_size = map.tile_size;
+        let edge = |tile: i32| tile_edge(tile, tile_size, zoom);
+        let origin = |tile: i32| ((tile as f64 * tile_size as f64) * zoom).floor() as i32;
+
+        // the tiles a chunk covers as (left, top, right, bottom). The chunks at the right and at
+        // the top of the map have a margin for the parts of larger tiles that reach beyond the map.
+        let chunk_count = |tiles: usize| (tiles as f64 / chunk_size as f64).ceil() as i32;
+        let chunk_columns = chunk_count(map.column_count());
+        let chunk_rows = chunk_count(map.row_count());
+        let chunk_tiles = |x: i32, y: i32| {
+            let size = chunk_size as i32;
+            let right = if x == chunk_columns - 1 {
+                cmp::max(
+                    (x + 1) * size,
+                    (map.column_count() + overhang_columns) as i32,
+                )
+            } else {
+                (x + 1) * size
+            };
+            let top = if y == 0 {
+                -(overhang_rows as i32)
+            } else {
+                y * size
+            };
+
+            (x * size, top, right, (y + 1) * size)
+        };
+
+        // the range of chunks that intersects the camera, the edges of the chunks are rounded and
+        // the margins of the chunks at the edges of the map are checked for each chunk
+        let chunk_pixels = chunk_size as f64 * tile_size as f64 * zoom;
+        let (start_x, start_y, end_x, end_y) = if chunk_pixels <= 0.0 {
+            (0, 0, 0, 0)
+        } else {
+            let first = |position: i32| cmp::max(0, (position as f64 / chunk_pixels) as i32 - 1);
+            let last = |position: i32, size: u32| {
+                ((position as f64 + size as f64) / chunk_pixels).ceil() as i32 + 1
+            };
+
+            (
+                cmp::max(0, cmp::min(chunk_columns - 1, first(position.0))),
+                first(position.1),
+                cmp::min(chunk_columns, last(position.0, size.0)),
+                cmp::min(chunk_rows, cmp::max(1, last(position.1, size.1))),
+            )
+        };
+        let visible = |x: i32, y: i32| {
+            let (left, top, right, bottom) = chunk_tiles(x, y);
+
+            edge(right) > position.0
+                && edge(left) < position.0 + size.0 as i32
+                && edge(bottom) > position.1
+                && edge(top) < position.1 + size.1 as i32
+        };
+
+        // chunks next to the camera are kept for scrolling back
+        buffer.chunks.retain(|&(x, y), _| {
+            x as i32 >= start_x - 1
+                && x as i32 <= end_x
+                && y as i32 >= start_y - 1
+                && y as i32 <= end_y
+        });
+
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                if !visible(x, y) {
+                    continue;
+                }
+
+                // the tiles of the chunk and the larger tiles of its neighbors in the margin that
+                // reach into it
+                let key = (x as usize, y as usize);
+                let columns =
+                    (key.0 * chunk_size).saturating_sub(overhang_columns)..(key.0 + 1) * chunk_size;
+                let rows = key.1 * chunk_size..(key.1 + 1) * chunk_size + overhang_rows;
+
+                let redraw = match buffer.chunks.get(&key) {
+                    Some(chunk) => {
+                        chunk.render_targets.len() != chunked_count
+                            || map.animation_changed_in(columns, rows, chunk.elapsed, elapsed)
+                    }
+                    None => true,
+                };
+
+                if !redraw {
+                    continue;
+                }
+
+                // the chunk reaches from its camera to the first tile of the next chunk
+                let (left, top, right, bottom) = chunk_tiles(x, y);
+                let chunk_width = edge(right) - origin(left);
+                let chunk_height = edge(bottom) - origin(top);
+
+                let mut render_targets = buffer
+                    .chunks
+                    .remove(&key)
+                    .map(|chunk| chunk.render_targets)
+                    .unwrap_or_default();
+                render_targets.truncate(chunked_count);
+
+                for render_target in &mut render_targets {
+                    if render_target.width() as i32 == chunk_width
+                        && render_target.height() as i32 == chunk_height
+                    {
+                        for pixel in render_target.data_mut() {
+                            *pixel = 0;
+                        }
+                    } else {
+                        *render_target = RenderTarget::new(chunk_width as u32, chunk_height as u32);
+                    }
+                }
+
+                while render_targets.len() < chunked_count {
+                    render_targets.push(RenderTarget::new(chunk_width as u32, chunk_height as u32));
+                }
+
+                // the tiles in the margin of the chunk are culled by the camera like the tiles of
+                // the chunk, so the parts of larger tiles that reach into it are drawn
+                let chunk_camera = CameraBuilder::new()
+                    .x(left as f64 * tile_size as f64)
+                    .y(top as f64 * tile_size as f64)
+                    .width(chunk_width as f64)
+                    .height(chunk_height as f64)
+                    .zoom(zoom)
+                    .build();
+
+                // the layers of a group are blended from bottom to top
+                let layers = groups.iter().filter_map(|g| match g {
+                    LayerGroup::Chunked(layers) => Some(layers),
+                    LayerGroup::Direct(_) => None,
+                });
+
+                for (render_target, layers) in render_targets.iter_mut().zip(layers) {
+                    for &l in layers {
+                        TileMapRenderObject::draw_layer(
+                            render_target,
+                            map,
+                            l,
+                            &chunk_camera,
+                            image,
+                            images,
+                            elapsed,
+                        );
+                    }
+                }
+
+                buffer.chunks.insert(
+                    key,
+                    Chunk {
+                        render_targets,
+                        elapsed,
+                        offset: (edge(left) - origin(left), edge(top) - origin(top)),
+                    },
+                );
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return false;
+        }
+
+        if buffer.size != size {
+            buffer.render_target = RenderTarget::new(size.0, size.1);
+            buffer.size = size;
+        } else {
+            for pixel in buffer.render_target.data_mut() {
+                *pixel = 0;
+            }
+        }
+
+        TileMapRenderObject::draw_image_layers(&mut buffer.render_target, map, &camera, images);
+
+        // the first group is copied if nothing is below it, the following groups are blended
+        let mut blended = !map.image_layers.is_empty();
+        let mut index = 0;
+
+        for group in &groups {
+            match group {
+                LayerGroup::Direct(l) => TileMapRenderObject::draw_layer(
+                    &mut buffer.render_target,
+                    map,
+                    *l,
+                    &camera,
+                    image,
+                    images,
+                    elapsed,
+                ),
+                LayerGroup::Chunked(_) => {
+                    // only the tiles of a chunk are copied, the neighbors of the chunk own the
+                    // rest
+                    for y in start_y..end_y {
+                        for x in start_x..end_x {
+                            let chunk = match buffer.chunks.get(&(x as usize, y as usize)) {
+                                Some(chunk) => chunk,
+                                None => continue,
+                            };
+
+                            if let Some(render_target) = chunk.render_targets.get(index) {
+                                let (left, top, right, bottom) = chunk_tiles(x, y);
+
+                                copy_render_target(
+                                    &mut buffer.render_target,
+                                    render_target,
+                                    (
+                                        chunk.offset.0,
+                                        chunk.offset.1,
+                                        edge(right) - edge(left),
+                                        edge(bottom) - edge(top),
+                                    ),
+                                    edge(left) - position.0,
+                                    edge(top) - position.1,
+                                    blended,
+                                );
+                            }
+                        }
+                    }
+
+                    index += 1;
+                }
+            }
+
+            blended = true;
+        }
+
+        buffer.valid = true;
+        buffer.position = position;
+        buffer.elapsed = elapsed;
+
+        true
+    }
+
+    /// Gets the render target of the buffer.
+    pub fn render_target(&self) -> Ref<'_, RenderTarget> {
+        Ref::map(self.buffer.borrow(), |b| &b.render_target)
+    }
+
+    /// Sets the bounds of the tile map on the screen, i.e. its absolute position and size.
+    pub fn set_bounds(&self, bounds: Rectangle) {
+        self.buffer.borrow_mut().bounds = bounds;
+    }
+
+    /// Gets the bounds of the tile map on the screen it was last drawn at.
+    pub fn bounds(&self) -> Rectangle {
+        self.buffer.borrow().bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        headless::render_map,
+        render::Image,
+        utils::{Shake, SharedMap},
+    };
+
+    #[test]
+    fn test_update() {
+        let mut map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 4, tile_size: 2, blocked_tiles: [],
+                layers: [(tiles: [0, 0, 0, 1])],
+                tile_sets: [(tile_width: 2, tile_height: 2, tiles: [(id: 1, animation: [(tile: 0, duration: 10), (tile: 1, duration: 10)])])],
+            )",
+        )
+        .unwrap();
+        let image = Image::from_data(4, 2, vec![0xFF00_0001; 8]).unwrap();
+        let images = TileImages::from(vec![image]);
+        let mut camera = CameraBuilder::new().width(2.0).height(2.0).build();
+        let buffer = TileMapBuffer::with_chunk_size(1);
+
+        assert!(buffer.update(&map, &camera, None, &images, 0.0, (2, 2)));
+        assert_eq!(&[0xFF00_0001; 4], buffer.render_target().data());
+
+        // nothing has changed
+        assert!(!buffer
+            .clone()
+            .update(&map, &camera, None, &images, 0.0, (2, 2)));
+        assert!(!buffer.update(&map, &camera, None, &images.clone(), 5.0, (2, 2)));
+
+        // the animated tile is not visible
+        assert!(!buffer.update(&map, &camera, None, &images, 15.0, (2, 2)));
+
+        camera.set_position(6.0, 0.0);
+        assert!(buffer.update(&map, &camera, None, &images, 15.0, (2, 2)));
+        assert!(buffer.update(&map, &camera, None, &images, 25.0, (2, 2)));
+
+        map.set_tile(0, 3, 0, -1);
+        assert!(buffer.update(&map, &camera, None, &images, 25.0, (2, 2)));
+        assert_eq!(&[0; 4], buffer.render_target().data());
+
+        assert!(buffer.update(&map, &camera, None, &images, 25.0, (4, 2)));
+        assert!(!buffer.update(&map, &camera, None, &images, 25.0, (4, 2)));
+
+        let images = TileImages::default();
+        assert!(buffer.update(&map, &camera, None, &images, 25.0, (4, 2)));
+
+        buffer.invalidate();
+        assert!(buffer.update(&map, &camera, None, &images, 25.0, (4, 2)));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let buffer = TileMapBuffer::new();
+        assert_eq!(Rectangle::default(), buffer.bounds());
+
+        // the clones of the buffer share the bounds, e.g. the widget and its render object
+        let bounds = Rectangle::new(Point::new(100.0, 50.0), 8.0, 4.0);
+        buffer.clone().set_bounds(bounds);
+        assert_eq!(bounds, buffer.bounds());
+    }
+
+    #[test]
+    fn test_swap_image() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 2, tile_size: 1, blocked_tiles: [],
+                layers: [(tiles: [0, 1])],
+            )",
+        )
+        .unwrap();
+        let transparent = SharedImage::new(Image::from_data(2, 1, vec![0, 0]).unwrap());
+        let opaque =
+            SharedImage::new(Image::from_data(2, 1, vec![0xFF10_2030, 0xFF40_5060]).unwrap());
+        let images = TileImages::default();
+        let camera = CameraBuilder::new().width(2.0).height(1.0).build();
+        let buffer = TileMapBuffer::new();
+
+        buffer.update(&map, &camera, Some(&transparent), &images, 0.0, (2, 1));
+        assert_eq!(&[0, 0], buffer.render_target().data());
+
+        // the opacities of the transparent image of the same size are not used
+        buffer.update(&map, &camera, Some(&opaque), &images, 0.0, (2, 1));
+        assert_eq!(&[0xFF10_2030, 0xFF40_5060], buffer.render_target().data());
+
+        buffer.update(&map, &camera, Some(&transparent), &images, 0.0, (2, 1));
+        assert_eq!(&[0, 0], buffer.render_target().data());
+    }
+
+    #[test]
+    fn test_split_screen() {
+        let map = SharedMap::from(
+            Map::from_ron_str(
+                "(layer_count: 1, row_count: 1, column_count: 2, tile_size: 1, blocked_tiles: [],
+                    layers: [(tiles: [0, 1])],
+                )",
+            )
+            .unwrap(),
+        );
+        let transparent = SharedImage::new(Image::from_data(2, 1, vec![0, 0]).unwrap());
+        let opaque =
+            SharedImage::new(Image::from_data(2, 1, vec![0xFF10_2030, 0xFF40_5060]).unwrap());
+        let camera = CameraBuilder::new().width(2.0).height(1.0).build();
+
+        // both views share the map and the images but have their own image property
+        let images = TileImages::default();
+        let left = TileMapBuffer::new();
+        let right = TileMapBuffer::new();
+
+        for _ in 0..2 {
+            left.update(
+                &map.borrow(),
+                &camera,
+                Some(&transparent),
+                &images.clone(),
+                0.0,
+                (2, 1),
+            );
+            right.update(
+                &map.borrow(),
+                &camera,
+                Some(&opaque),
+                &images.clone(),
+                0.0,
+                (2, 1),
+            );
+
+            assert_eq!(&[0, 0], left.render_target().data());
+            assert_eq!(&[0xFF10_2030, 0xFF40_5060], right.render_target().data());
+        }
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut map = Map::from_ron_str(
+            "(layer_count: 2, row_count: 3, column_count: 5, tile_size: 2, blocked_tiles: [],
+                layers: [
+                    (tiles: [0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2]),
+                    (tiles: [-1, -1, 3, -1, -1, 2, -1, -1, -1, 1, -1, -1, 0, -1, -1]),
+                ],
+            )",
+        )
+        .unwrap();
+        let image = Image::from_data(4, 4, (0..16).map(|i| 0xFF00_0000 | i).collect()).unwrap();
+        let shared = SharedImage::from(image.clone());
+        let images = TileImages::default();
+        let buffer = TileMapBuffer::with_chunk_size(2);
+
+        // the chunks show the same as the layers drawn directly
+        for &zoom in &[1.0, 2.0, 0.75, 1.5] {
+            for y in -5..8 {
+                for x in -5..12 {
+                    let camera = CameraBuilder::new()
+                        .x(x as f64 * 0.75)
+                        .y(y as f64 * 0.75)
+                        .width(5.0)
+                        .height(3.0)
+                        .zoom(zoom)
+                        .build();
+
+                    let mut expected = RenderTarget::new(5, 3);
+
+                    for l in 0..2 {
+                        TileMapRenderObject::draw_layer(
+                            &mut expected,
+                            &map,
+                            l,
+                            &camera,
+                            Some(&shared),
+                            &images,
+                            0.0,
+                        );
+                    }
+
+                    buffer.update(&map, &camera, Some(&shared), &images, 0.0, (5, 3));
+                    assert_eq!(
+                        expected.data(),
+                        buffer.render_target().data(),
+                        "camera {}, {} with zoom {}",
+                        camera.x(),
+                        camera.y(),
+                        zoom
+                    );
+                }
+            }
+        }
+
+        // a changed tile only invalidates its chunk
+        let camera = CameraBuilder::new().width(8.0).height(4.0).build();
+        buffer.update(&map, &camera, Some(&shared), &images, 0.0, (8, 4));
+        buffer
+            .buffer
+            .borrow_mut()
+            .chunks
+            .get_mut(&(0, 0))
+            .unwrap()
+            .render_targets[0]
+            .data_mut()[0] = 0x1234;
+
+        map.set_tile(1, 2, 0, 0);
+        assert!(buffer.update(&map, &camera, Some(&shared), &images, 0.0, (8, 4)));
+        assert_eq!(0x1234, buffer.render_target().data()[0]);
+        assert_eq!(0xFF00_0000, buffer.render_target().data()[4]);
+
+        // hiding a layer at runtime invalidates all chunks
+        map.set_layer_visible(1, false);
+        assert!(buffer.update(&map, &camera, Some(&shared), &images, 0.0, (8, 4)));
+
+        let mut expected = RenderTarget::new(8, 4);
+        TileMapRenderObject::draw_layer(
+            &mut expected,
+            &map,
+            0,
+            &camera,
+            Some(&shared),
+            &images,
+            0.0,
+        );
+        assert_eq!(expected.data(), buffer.render_target().data());
+    }
+
+    #[test]
+    fn test_chunks_large_tiles() {
+        // tiles of 2 x 2 pixels on a map of 1 pixel tiles reach into the chunks to the right and
+        // above
+        let mut map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 3, column_count: 4, tile_size: 1, blocked_tiles: [],
+                layers: [(tiles: [0, -1, 1, -1, -1, 1, -1, 0, 1, -1, 0, -1])],
+                tile_sets: [(tile_width: 2, tile_height: 2)],
+            )",
+        )
+        .unwrap();
+        let image = Image::from_data(4, 2, (0..8).map(|i| 0xFF00_0000 | i).collect()).unwrap();
+        let shared = SharedImage::from(image);
+        let images = TileImages::default();
+        let buffer = TileMapBuffer::with_chunk_size(1);
+
+        let draw = |map: &Map, camera: &Camera| {
+            let mut expected = RenderTarget::new(3, 2);
+            TileMapRenderObject::draw_layer(
+                &mut expected,
+                map,
+                0,
+                camera,
+                Some(&shared),
+                &images,
+                0.0,
+            );
+            expected
+        };
+
+        // the chunks show the same as the layer drawn directly, also beyond the map
+        for &zoom in &[1.0, 2.0, 1.5] {
+            for y in -3..5 {
+                for x in -3..7 {
+                    let camera = CameraBuilder::new()
+                        .x(x as f64 * 0.75)
+                        .y(y as f64 * 0.75)
+                        .width(3.0)
+                        .height(2.0)
+                        .zoom(zoom)
+                        .build();
+
+                    buffer.update(&map, &camera, Some(&shared), &images, 0.0, (3, 2));
+                    assert_eq!(
+                        draw(&map, &camera).data(),
+                        buffer.render_target().data(),
+                        "camera {}, {} with zoom {}",
+                        camera.x(),
+                        camera.y(),
+                        zoom
+                    );
+                }
+            }
+        }
+
+        // a changed tile invalidates the chunks its tile reaches into
+        let camera = CameraBuilder::new().width(3.0).height(2.0).build();
+        buffer.update(&map, &camera, Some(&shared), &images, 0.0, (3, 2));
+        map.set_tile(0, 1, 1, 0);
+        assert!(buffer.update(&map, &camera, Some(&shared), &images, 0.0, (3, 2)));
+        assert_eq!(draw(&map, &camera).data(), buffer.render_target().data());
+    }
+
+    #[test]
+    fn test_shake() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 2, column_count: 2, tile_size: 2, blocked_tiles: [],
+                layers: [(tiles: [0, 1, 1, 0])],
+            )",
+        )
+        .unwrap();
+        let image = Image::from_data(4, 2, (0..8).map(|i| 0xFF00_0000 | i).collect()).unwrap();
+        let shared = SharedImage::from(image.clone());
+        let images = TileImages::default();
+        let buffer = TileMapBuffer::with_chunk_size(1);
+        let mut camera = CameraBuilder::new()
+            .width(4.0)
+            .height(4.0)
+            .shake(Shake::new(1))
+            .build();
+
+        buffer.update(&map, &camera, Some(&shared), &images, 0.0, (4, 4));
+        let still = buffer.render_target().data().to_vec();
+
+        camera.add_trauma(1.0);
+        camera.update_shake(0.1);
+        assert!(buffer.update(&map, &camera, Some(&shared), &images, 0.0, (4, 4)));
+
+        // the map is drawn at the shaken position
+        let mut expected = RenderTarget::new(4, 4);
+        TileMapRenderObject::draw_layer(
+            &mut expected,
+            &map,
+            0,
+            &camera,
+            Some(&shared),
+            &images,
+            0.0,
+        );
+        assert_eq!(expected.data(), buffer.render_target().data());
+        assert_ne!(&still[..], buffer.render_target().data());
+    }
+
+    #[test]
+    fn test_parallax() {
+        let mut map = Map::from_ron_str(
+            "(layer_count: 3, row_count: 3, column_count: 5, tile_size: 2, blocked_tiles: [],
+                layers: [
+                    (tiles: [0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2]),
+                    (parallax: (0.5, 0.25), tiles: [-1, 1, -1, 1, -1, 3, -1, 3, -1, 3, -1, 1, -1, 1, -1]),
+                    (tiles: [-1, -1, 3, -1, -1, 2, -1, -1, -1, 1, -1, -1, 0, -1, -1]),
+                ],
+                image_layers: [(parallax: (0.25, 0.0), repeat_x: true)],
+            )",
+        )
+        .unwrap();
+        let image = Image::from_data(
+            4,
+            4,
+            (0..16)
+                .map(|i| {
+                    if i % 3 == 0 {
+                        0x8000_0000 | i
+                    } else {
+                        0xFF00_0000 | i
+                    }
+                })
+                .collect(),
+        )
+        .unwrap();
+        let shared = SharedImage::from(image.clone());
+        let background =
+            Image::from_data(3, 1, vec![0xFF00_0100, 0xFF00_0200, 0xFF00_0300]).unwrap();
+        let images = TileImages::default().with_layer_images(vec![Some(background)]);
+        let buffer = TileMapBuffer::with_chunk_size(2);
+
+        // the buffer shows the same as the image layer and the layers drawn directly
+        for &zoom in &[1.0, 2.0] {
+            for y in -3..6 {
+                for x in -3..10 {
+                    let camera = CameraBuilder::new()
+                        .x(x as f64 * 0.75)
+                        .y(y as f64 * 0.75)
+                        .width(5.0)
+                        .height(3.0)
+                        .zoom(zoom)
+                        .build();
+
+                    buffer.update(&map, &camera, Some(&shared), &images, 0.0, (5, 3));
+                    assert_eq!(
+                        render_map(&map, &camera, Some(&shared), &images, 0.0).data(),
+                        buffer.render_target().data(),
+                        "camera {}, {} with zoom {}",
+                        camera.x(),
+                        camera.y(),
+                        zoom
+                    );
+                }
+            }
+        }
+
+        // the parallax layer becomes part of the chunks, which are not blended over a background
+        map.set_layer_parallax(1, (1.0, 1.0));
+        map.image_layers.clear();
+        let camera = CameraBuilder::new().x(1.0).width(5.0).height(3.0).build();
+        assert!(buffer.update(&map, &camera, Some(&shared), &images, 0.0, (5, 3)));
+        assert_eq!(
+            render_map(&map, &camera, Some(&shared), &images, 0.0).data(),
+            buffer.render_target().data()
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Measures the time to find paths on large maps.
+//!
+//! Run with `cargo bench -p orbgame-utils`.
+
+use std::time::{Duration, Instant};
+
+use orbgame_utils::*;
+
+const RUNS: u32 = 10;
+
+// A map with walls in every fourth row that have a gap at alternating ends.
+fn map(size: usize) -> Map {
+    let tiles = (0..size * size)
+        .map(|i| {
+            let (column, row) = (i % size, i / size);
+            let gap = if row % 8 == 2 { size - 1 } else { 0 };
+
+            if row % 4 == 2 && column != gap {
+                1
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    Map {
+        layer_count: 1,
+        row_count: size,
+        column_count: size,
+        tile_size: 32,
+        blocked_tiles: vec![1],
+        layers: vec![Layer::new("ground", tiles)],
+        ..Map::default()
+    }
+}
+
+fn bench<F: FnMut()>(name: &str, mut run: F) {
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        run();
+    }
+
+    let elapsed: Duration = start.elapsed();
+    println!(
+        "{:<50} {:>10.3} ms per path",
+        name,
+        elapsed.as_secs_f64() * 1000.0 / RUNS as f64
+    );
+}
+
+fn main() {
+    for &size in &[256, 1024] {
+        let mut map = map(size);
+        let options = |movement| {
+            PathOptions::new()
+                .movement(movement)
+                .corner_cutting(CornerCutting::Never)
+        };
+
+        // across the map in a straight line
+        bench(&format!("{0} x {0}, straight", size), || {
+            map.find_path((0, 0), (size - 1, 0), &options(Movement::FourWay))
+                .unwrap();
+        });
+
+        // through the gaps of all walls
+        for &movement in &[Movement::FourWay, Movement::EightWay] {
+            bench(&format!("{0} x {0}, winding {1:?}", size, movement), || {
+                map.find_path((0, 0), (size - 1, size - 1), &options(movement))
+                    .unwrap();
+            });
+        }
+
+        // the goal is enclosed, the whole map is visited
+        map.set_tile(0, size - 2, size - 1, 1);
+        map.set_tile(0, size - 1, size - 2, 1);
+        map.set_tile(0, size - 2, size - 2, 1);
+        bench(&format!("{0} x {0}, unreachable", size), || {
+            assert!(map
+                .find_path((0, 0), (size - 1, size - 1), &options(Movement::FourWay))
+                .is_none());
+        });
+
+        // the search gives up after a number of visited tiles
+        bench(
+            &format!("{0} x {0}, unreachable, 65536 visited", size),
+            || {
+                assert!(map
+                    .find_path(
+                        (0, 0),
+                        (size - 1, size - 1),
+                        &options(Movement::FourWay).max_visited(65536)
+                    )
+                    .is_none());
+            },
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::{cell::Cell, rc::Rc};
+
+use serde_derive::{Deserialize, Serialize};
+
+use orbtk::prelude::*;
+
+/// Describes one frame of an animated tile.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Frame {
+    /// The id of the tile that is drawn for the frame, relative to the tile set of the animation.
+    pub tile: u32,
+
+    /// The duration of the frame in milliseconds.
+    pub duration: u32,
+}
+
+impl Frame {
+    /// Creates a new frame.
+    pub fn new(tile: u32, duration: u32) -> Self {
+        Frame { tile, duration }
+    }
+}
+
+/// Gets the frame of the given looping frame sequence after the elapsed milliseconds.
+pub fn current_frame(frames: &[Frame], elapsed: f64) -> Option<&Frame> {
+    let duration: u64 = frames.iter().map(|f| f.duration as u64).sum();
+
+    if duration == 0 {
+        return frames.first();
+    }
+
+    let mut time = elapsed.max(0.0) as u64 % duration;
+
+    for frame in frames {
+        if time < frame.duration as u64 {
+            return Some(frame);
+        }
+
+        time -= frame.duration as u64;
+    }
+
+    frames.last()
+}
+
+/// The elapsed-time clock of tile animations. The game advances the clock and all clones of the
+/// clock share the same time.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct AnimationClock {
+    elapsed: Rc<Cell<f64>>,
+}
+
+into_property_source!(AnimationClock);
+
+impl AnimationClock {
+    /// Creates a new clock starting at 0.
+    pub fn new() -> Self {
+        AnimationClock::default()
+    }
+
+    /// Gets the elapsed time in milliseconds.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed.get()
+    }
+
+    /// Advances the clock by the given milliseconds.
+    pub fn advance(&self, delta: f64) {
+        self.elapsed.set(self.elapsed.get() + delta);
+    }
+
+    /// Resets the clock to 0.
+    pub fn reset(&self) {
+        self.elapsed.set(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_current_frame() {
+        let frames = vec![Frame::new(4, 100), Frame::new(5, 50)];
+
+        assert_eq!(4, current_frame(&frames, 0.0).unwrap().tile);
+        assert_eq!(5, current_frame(&frames, 120.0).unwrap().tile);
+        assert_eq!(4, current_frame(&frames, 150.0).unwrap().tile);
+        assert_eq!(5, current_frame(&frames, 1040.0).unwrap().tile);
+        assert_eq!(None, current_frame(&[], 10.0));
+    }
+
+    #[test]
+    fn test_clock() {
+        let clock = AnimationClock::new();
+        let shared = clock.clone();
+
+        clock.advance(16.0);
+        shared.advance(16.0);
+        assert_eq!(32.0, clock.elapsed());
+
+        shared.reset();
+        assert_eq!(0.0, clock.elapsed());
+    }
+}
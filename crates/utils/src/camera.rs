@@ -1,11 +1,35 @@
 use orbtk::prelude::*;
 
+use crate::{Map, Shake};
+
 /// Used to build a camera, specifying additional details.
-#[derive(Clone, Default, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CameraBuilder {
     rect: Rectangle,
     maximum: Point,
     speed: f64,
+    zoom: f64,
+    fit_map: bool,
+    dead_zone: Rectangle,
+    smoothing: f64,
+    look_ahead: f64,
+    shake: Shake,
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        CameraBuilder {
+            rect: Rectangle::default(),
+            maximum: Point::default(),
+            speed: 0.0,
+            zoom: 1.0,
+            fit_map: false,
+            dead_zone: Rectangle::default(),
+            smoothing: 0.0,
+            look_ahead: 0.0,
+            shake: Shake::default(),
+        }
+    }
 }
 
 impl CameraBuilder {
@@ -56,24 +80,93 @@ impl CameraBuilder {
         self
     }
 
+    /// Inserts a zoom.
+    pub fn zoom(mut self, zoom: f64) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// Inserts a fit_map.
+    pub fn fit_map(mut self, fit_map: bool) -> Self {
+        self.fit_map = fit_map;
+        self
+    }
+
+    /// Inserts a dead_zone.
+    pub fn dead_zone(mut self, dead_zone: Rectangle) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+
+    /// Inserts a smoothing.
+    pub fn smoothing(mut self, smoothing: f64) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Inserts a look_ahead.
+    pub fn look_ahead(mut self, look_ahead: f64) -> Self {
+        self.look_ahead = look_ahead;
+        self
+    }
+
+    /// Inserts a shake.
+    pub fn shake(mut self, shake: Shake) -> Self {
+        self.shake = shake;
+        self
+    }
+
     /// Builds the camera.
     pub fn build(self) -> Camera {
         Camera {
             rect: self.rect,
             maximum: self.maximum,
             speed: self.speed,
+            zoom: self.zoom,
+            fit_map: self.fit_map,
+            world_size: None,
+            dead_zone: self.dead_zone,
+            smoothing: self.smoothing,
+            look_ahead: self.look_ahead,
+            shake: self.shake,
+            target: None,
+            velocity: Point::default(),
         }
     }
 }
 
 /// The camera is use to describes the viewport on a screen like a part of a tile map.
 ///
-/// The camera can be moved.
-#[derive(Clone, Default, Debug, PartialEq)]
+/// The camera can be moved or follow a target. The position of the camera is in world
+/// coordinates, the size in screen pixels. A zoom of 2 shows the world twice as large, so the
+/// camera sees half of the width and the height of the world.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Camera {
     rect: Rectangle,
     maximum: Point,
     speed: f64,
+    zoom: f64,
+    fit_map: bool,
+
+    // the size of the world the camera is constrained to
+    world_size: Option<(f64, f64)>,
+
+    dead_zone: Rectangle,
+    smoothing: f64,
+    look_ahead: f64,
+    shake: Shake,
+
+    // the center of the followed target on the last follow
+    target: Option<Point>,
+
+    // the velocity of the smoothed camera movement
+    velocity: Point,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        CameraBuilder::default().build()
+    }
 }
 
 into_property_source!(Camera);
@@ -90,6 +183,7 @@ impl Camera {
             rect,
             maximum,
             speed: 256.0,
+            ..Camera::default()
         }
     }
 
@@ -100,7 +194,7 @@ impl Camera {
 
     /// Sets x.
     pub fn set_x(&mut self, x: f64) {
-        self.rect.set_y(x);
+        self.rect.set_x(x);
     }
 
     /// Gets y.
@@ -182,55 +276,329 @@ impl Camera {
         self.rect
             .set_y(self.rect.y() + (dir_y as f64 * self.speed as f64 * delta) as f64);
 
-        let zero: f64 = 0.0;
+        self.clamp();
+    }
+
+    /// Gets zoom.
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// Sets the zoom, values of 0 or less are ignored. The position is kept.
+    pub fn set_zoom(&mut self, zoom: f64) {
+        if zoom > 0.0 {
+            self.zoom = zoom;
+        }
+    }
+
+    /// Sets the zoom and keeps the world position below the given screen position, e.g. below the
+    /// mouse cursor. The camera is clamped to its bounds.
+    pub fn zoom_at(&mut self, zoom: f64, screen_position: Point) {
+        let world_position = self.screen_to_world(screen_position);
+        self.set_zoom(zoom);
 
-        // adjust to respect the render_camera
         self.rect
-            .set_x(zero.max(self.rect.x().min(self.maximum.x())));
+            .set_x(world_position.x() - screen_position.x() / self.zoom);
         self.rect
-            .set_y(zero.max(self.rect.y().min(self.maximum.y())));
-    }
-
-    // pub fn follow(&mut self, entity: &mut Entity) {
-    //     let mut screen_position = entity.screen_position().get();
-    //     let entity_rect = entity.rect().get();
-    //     let mut rect = self.rect.get();
-    //     let maximum = self.maximum.get();
-
-    //     screen_position.x = rect.width as f64 / 2.0;
-    //     screen_position.y = rect.height as f64 / 2;
-
-    //     // make the camera follow the sprite
-    //     rect.x = entity_rect.x - rect.width as f64 / 2;
-    //     rect.y = entity_rect.y - rect.height as f64 / 2;
-
-    //     let zero: f64 = 0;
-
-    //     // clamp values
-    //     rect.x = zero.max(rect.x.min(maximum.x));
-    //     rect.y = zero.max(rect.y.min(maximum.y));
-
-    //     // in map corners, the sprite cannot be placed in the center of the screen
-    //     // and we have to change its screen coordinates
-
-    //     // left and right sides
-    //     if entity_rect.x < rect.width as f64 / 2
-    //         || entity_rect.x > maximum.x + rect.width as f64 / 2
-    //     {
-    //         let new_x = entity_rect.x - rect.x;
-    //         screen_position.x = new_x;
-    //     }
-    //     // top and bottom sides
-    //     if entity_rect.y < rect.height as f64 / 2
-    //         || entity_rect.y > maximum.y + rect.height as f64 / 2
-    //     {
-    //         let new_y = entity_rect.y - rect.y;
-    //         screen_position.y = new_y;
-    //     }
-
-    //     entity.screen_position().set(screen_position);
-    //     self.rect.set(rect);
-    // }
+            .set_y(world_position.y() - screen_position.y() / self.zoom);
+        self.clamp();
+    }
+
+    /// Gets the width of the visible part of the world.
+    pub fn view_width(&self) -> f64 {
+        self.rect.width() / self.zoom
+    }
+
+    /// Gets the height of the visible part of the world.
+    pub fn view_height(&self) -> f64 {
+        self.rect.height() / self.zoom
+    }
+
+    /// Converts a position in the world to a position on the screen relative to the camera. The
+    /// shake of the camera is included, like on rendering.
+    pub fn world_to_screen(&self, position: Point) -> Point {
+        let render_position = self.render_position();
+
+        Point::new(
+            position.x() * self.zoom - render_position.x(),
+            position.y() * self.zoom - render_position.y(),
+        )
+    }
+
+    /// Converts a position on the screen relative to the camera to a position in the world. The
+    /// shake of the camera is included, like on rendering.
+    pub fn screen_to_world(&self, position: Point) -> Point {
+        let render_position = self.render_position();
+
+        Point::new(
+            (position.x() + render_position.x()) / self.zoom,
+            (position.y() + render_position.y()) / self.zoom,
+        )
+    }
+
+    /// Gets fit map.
+    pub fn fit_map(&self) -> bool {
+        self.fit_map
+    }
+
+    /// If set the `TileMap` widget keeps the size of the camera equal to its own size and
+    /// constrains the camera to its map, see `constrain_to_map`.
+    pub fn set_fit_map(&mut self, fit_map: bool) {
+        self.fit_map = fit_map;
+    }
+
+    /// Gets the size of the world the camera is constrained to.
+    pub fn world_size(&self) -> Option<(f64, f64)> {
+        self.world_size
+    }
+
+    /// Constrains the camera to a world of the given size instead of the maximum. If the world is
+    /// smaller than the visible part of the world, the world is centered.
+    pub fn set_world_size(&mut self, width: f64, height: f64) {
+        self.world_size = Some((width, height));
+        self.clamp();
+    }
+
+    /// Constrains the camera to the pixel size of the map, see `set_world_size`.
+    pub fn constrain_to_map(&mut self, map: &Map) {
+        self.set_world_size(
+            map.column_count() as f64 * map.tile_size as f64,
+            map.row_count() as f64 * map.tile_size as f64,
+        );
+    }
+
+    /// Removes the constraint to the world size, the camera is constrained by the maximum again.
+    pub fn clear_world_size(&mut self) {
+        self.world_size = None;
+    }
+
+    // Gets the smallest and the largest position of the camera. The maximum describes the largest
+    // position at a zoom of 1, a larger zoom shows less of the world and moves the limit further.
+    fn bounds(&self) -> ((f64, f64), (f64, f64)) {
+        let axis = |world: f64, view: f64| {
+            let extra = world - view;
+
+            if extra < 0.0 {
+                (extra / 2.0, extra / 2.0)
+            } else {
+                (0.0, extra)
+            }
+        };
+
+        match self.world_size {
+            Some((width, height)) => (
+                axis(width, self.view_width()),
+                axis(height, self.view_height()),
+            ),
+            None => (
+                (
+                    0.0,
+                    (self.maximum.x() + self.rect.width() - self.view_width()).max(0.0),
+                ),
+                (
+                    0.0,
+                    (self.maximum.y() + self.rect.height() - self.view_height()).max(0.0),
+                ),
+            ),
+        }
+    }
+
+    /// Keeps the position of the camera inside of its bounds, e.g. after the size changed.
+    pub fn clamp(&mut self) {
+        let ((min_x, max_x), (min_y, max_y)) = self.bounds();
+
+        self.rect.set_x(self.rect.x().min(max_x).max(min_x));
+        self.rect.set_y(self.rect.y().min(max_y).max(min_y));
+    }
+
+    /// Centers the view of the camera on the given world position and keeps it inside of its
+    /// bounds.
+    pub fn center_on(&mut self, position: Point) {
+        self.set_position(
+            position.x() - self.view_width() / 2.0,
+            position.y() - self.view_height() / 2.0,
+        );
+        self.clamp();
+    }
+
+    /// Gets shake.
+    pub fn shake(&self) -> &Shake {
+        &self.shake
+    }
+
+    /// Sets shake.
+    pub fn set_shake(&mut self, shake: Shake) {
+        self.shake = shake;
+    }
+
+    /// Adds trauma to the shake of the camera, e.g. for a hit or an explosion.
+    pub fn add_trauma(&mut self, trauma: f64) {
+        self.shake.add_trauma(trauma);
+    }
+
+    /// Advances the shake of the camera by delta seconds.
+    pub fn update_shake(&mut self, delta: f64) {
+        self.shake.update(delta);
+    }
+
+    /// Gets the position of the camera on the screen in pixels that is used to render the camera.
+    /// It is scaled by the zoom and moved by the shake, which does not change the position of
+    /// the camera.
+    pub fn render_position(&self) -> Point {
+        self.parallax_position((1.0, 1.0))
+    }
+
+    /// Gets the render position of a layer that follows the camera by the given factor in x and y,
+    /// see `render_position`. A factor below 1.0 moves the layer slower than the camera.
+    pub fn parallax_position(&self, parallax: (f64, f64)) -> Point {
+        let offset = self.shake.offset();
+
+        Point::new(
+            self.rect.x() * parallax.0 * self.zoom + offset.x(),
+            self.rect.y() * parallax.1 * self.zoom + offset.y(),
+        )
+    }
+
+    /// Gets dead zone.
+    pub fn dead_zone(&self) -> &Rectangle {
+        &self.dead_zone
+    }
+
+    /// Sets the dead zone, the area of the screen the followed target can move inside without
+    /// moving the camera. An empty dead zone keeps the target centered.
+    pub fn set_dead_zone(&mut self, dead_zone: Rectangle) {
+        self.dead_zone = dead_zone;
+    }
+
+    /// Gets smoothing.
+    pub fn smoothing(&self) -> f64 {
+        self.smoothing
+    }
+
+    /// Sets the time constant in seconds of the critically damped following. 0 follows the target
+    /// without delay.
+    pub fn set_smoothing(&mut self, smoothing: f64) {
+        self.smoothing = smoothing;
+    }
+
+    /// Gets look ahead.
+    pub fn look_ahead(&self) -> f64 {
+        self.look_ahead
+    }
+
+    /// Sets the time in seconds the followed target is looked ahead in the direction of its
+    /// motion.
+    pub fn set_look_ahead(&mut self, look_ahead: f64) {
+        self.look_ahead = look_ahead;
+    }
+
+    /// Forgets the motion of the followed target and the camera, e.g. after the target teleports.
+    pub fn reset_follow(&mut self) {
+        self.target = None;
+        self.velocity = Point::default();
+    }
+
+    /// Moves the camera to follow the target rectangle after delta seconds. The camera is clamped
+    /// to its bounds.
+    ///
+    /// Returns the screen position of the target. Next to the edges of the map the target is not
+    /// in the center or the dead zone of the screen and has to be drawn at this position.
+    pub fn follow(&mut self, delta: f64, target: Rectangle) -> Point {
+        let center = Point::new(
+            target.x() + target.width() / 2.0,
+            target.y() + target.height() / 2.0,
+        );
+
+        // look ahead in the direction of the motion of the target
+        let focus = match self.target {
+            Some(last) if delta > 0.0 => Point::new(
+                center.x() + (center.x() - last.x()) / delta * self.look_ahead,
+                center.y() + (center.y() - last.y()) / delta * self.look_ahead,
+            ),
+            _ => center,
+        };
+        self.target = Some(center);
+
+        // the dead zone in world coordinates relative to the camera
+        let dead_zone = if self.dead_zone.width() <= 0.0 && self.dead_zone.height() <= 0.0 {
+            Rectangle::new(
+                Point::new(self.view_width() / 2.0, self.view_height() / 2.0),
+                0.0,
+                0.0,
+            )
+        } else {
+            Rectangle::new(
+                Point::new(
+                    self.dead_zone.x() / self.zoom,
+                    self.dead_zone.y() / self.zoom,
+                ),
+                self.dead_zone.width() / self.zoom,
+                self.dead_zone.height() / self.zoom,
+            )
+        };
+
+        let ((min_x, max_x), (min_y, max_y)) = self.bounds();
+        let goal_x = dead_zone_goal(self.rect.x(), focus.x(), dead_zone.x(), dead_zone.width())
+            .min(max_x)
+            .max(min_x);
+        let goal_y = dead_zone_goal(self.rect.y(), focus.y(), dead_zone.y(), dead_zone.height())
+            .min(max_y)
+            .max(min_y);
+
+        if self.smoothing <= 0.0 || delta <= 0.0 {
+            self.rect.set_x(goal_x);
+            self.rect.set_y(goal_y);
+            self.velocity = Point::default();
+        } else {
+            let (x, velocity_x) = smooth_damp(
+                self.rect.x(),
+                goal_x,
+                self.velocity.x(),
+                self.smoothing,
+                delta,
+            );
+            let (y, velocity_y) = smooth_damp(
+                self.rect.y(),
+                goal_y,
+                self.velocity.y(),
+                self.smoothing,
+                delta,
+            );
+
+            self.rect.set_x(x);
+            self.rect.set_y(y);
+            self.velocity = Point::new(velocity_x, velocity_y);
+            self.clamp();
+        }
+
+        self.world_to_screen(Point::new(target.x(), target.y()))
+    }
+}
+
+// Gets the camera position on one axis that moves the focus just inside of the dead zone.
+fn dead_zone_goal(position: f64, focus: f64, start: f64, length: f64) -> f64 {
+    if focus < position + start {
+        focus - start
+    } else if focus > position + start + length {
+        focus - start - length
+    } else {
+        position
+    }
+}
+
+// Moves the value towards the goal like a critically damped spring with the given time constant.
+// Returns the new value and velocity.
+fn smooth_damp(value: f64, goal: f64, velocity: f64, time: f64, delta: f64) -> (f64, f64) {
+    let omega = 2.0 / time;
+    let x = omega * delta;
+    let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+    let change = value - goal;
+    let temp = (velocity + omega * change) * delta;
+
+    (
+        goal + (change + temp) * decay,
+        (velocity - omega * temp) * decay,
+    )
 }
 
 #[cfg(test)]
@@ -325,5 +693,242 @@ mod tests {
             Camera::create().max_height(10.0).build().maximum().y()
         );
         assert_eq!(11.0, Camera::create().speed(11.0).build().speed());
+        assert_eq!(12.0, Camera::create().smoothing(12.0).build().smoothing());
+        assert_eq!(13.0, Camera::create().look_ahead(13.0).build().look_ahead());
+        assert_eq!(2.0, Camera::create().zoom(2.0).build().zoom());
+        assert!(Camera::create().fit_map(true).build().fit_map());
+        assert_eq!(1.0, Camera::default().zoom());
+    }
+
+    #[test]
+    fn test_zoom() {
+        let mut camera = Camera::create()
+            .x(10.0)
+            .y(20.0)
+            .width(100.0)
+            .height(50.0)
+            .max_width(200.0)
+            .max_height(100.0)
+            .speed(1000.0)
+            .zoom(2.0)
+            .build();
+
+        assert_eq!((50.0, 25.0), (camera.view_width(), camera.view_height()));
+        assert_eq!(
+            Point::new(10.0, 20.0),
+            camera.world_to_screen(Point::new(15.0, 30.0))
+        );
+        assert_eq!(
+            Point::new(15.0, 30.0),
+            camera.screen_to_world(Point::new(10.0, 20.0))
+        );
+
+        // the maximum grows by the hidden part of the world
+        camera.mov(1.0, 1.0, 1.0);
+        assert_eq!((250.0, 125.0), camera.position());
+
+        // the world position below the screen position is kept
+        camera.set_position(100.0, 50.0);
+        camera.zoom_at(4.0, Point::new(20.0, 10.0));
+        assert_eq!((105.0, 52.5), camera.position());
+        assert_eq!(
+            Point::new(110.0, 55.0),
+            camera.screen_to_world(Point::new(20.0, 10.0))
+        );
+
+        // zoomed out the camera is clamped
+        camera.zoom_at(0.5, Point::new(0.0, 0.0));
+        assert_eq!((100.0, 50.0), camera.position());
+
+        camera.set_zoom(0.0);
+        assert_eq!(0.5, camera.zoom());
+    }
+
+    fn target(x: f64, y: f64) -> Rectangle {
+        Rectangle::new(Point::new(x, y), 2.0, 2.0)
+    }
+
+    #[test]
+    fn test_center_on() {
+        let mut camera = Camera::create()
+            .width(100.0)
+            .height(50.0)
+            .zoom(2.0)
+            .max_width(200.0)
+            .max_height(200.0)
+            .build();
+
+        camera.center_on(Point::new(100.0, 60.0));
+        assert_eq!((75.0, 47.5), camera.position());
+
+        // the camera stays inside of its bounds
+        camera.center_on(Point::new(0.0, 0.0));
+        assert_eq!((0.0, 0.0), camera.position());
+    }
+
+    #[test]
+    fn test_parallax_position() {
+        let camera = Camera::create().x(10.0).y(20.0).zoom(2.0).build();
+
+        assert_eq!(
+            camera.render_position(),
+            camera.parallax_position((1.0, 1.0))
+        );
+        assert_eq!(Point::new(10.0, 0.0), camera.parallax_position((0.5, 0.0)));
+    }
+
+    #[test]
+    fn test_shake() {
+        let mut camera = Camera::create()
+            .x(10.0)
+            .y(20.0)
+            .zoom(2.0)
+            .shake(Shake::new(3))
+            .build();
+        assert_eq!(Point::new(20.0, 40.0), camera.render_position());
+
+        camera.add_trauma(1.0);
+        camera.update_shake(0.1);
+        let offset = camera.shake().offset();
+        assert_ne!(Point::default(), offset);
+
+        // the shake only moves the rendered position
+        assert_eq!((10.0, 20.0), camera.position());
+        assert_eq!(
+            Point::new(20.0 + offset.x(), 40.0 + offset.y()),
+            camera.render_position()
+        );
+        let screen = camera.world_to_screen(Point::new(10.0, 20.0));
+        assert!((screen.x() + offset.x()).abs() < 1e-9 && (screen.y() + offset.y()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_constrain_to_map() {
+        let map = Map {
+            row_count: 10,
+            column_count: 20,
+            tile_size: 8,
+            ..Map::default()
+        };
+        let mut camera = Camera::create().width(100.0).height(100.0).build();
+        camera.set_position(500.0, -5.0);
+        camera.constrain_to_map(&map);
+
+        assert_eq!(Some((160.0, 80.0)), camera.world_size());
+
+        // the map is smaller than the camera vertically and centered
+        assert_eq!((60.0, -10.0), camera.position());
+
+        // the bounds follow the size and the zoom of the camera
+        camera.set_size(50.0, 50.0);
+        camera.set_position(500.0, 500.0);
+        camera.clamp();
+        assert_eq!((110.0, 30.0), camera.position());
+
+        camera.set_zoom(0.5);
+        camera.clamp();
+        assert_eq!((60.0, -10.0), camera.position());
+
+        camera.clear_world_size();
+        camera.clamp();
+        assert_eq!((0.0, 0.0), camera.position());
+    }
+
+    #[test]
+    fn test_follow() {
+        let mut camera = Camera::create()
+            .width(10.0)
+            .height(10.0)
+            .max_width(100.0)
+            .max_height(50.0)
+            .build();
+
+        // the target is centered
+        assert_eq!(Point::new(4.0, 4.0), camera.follow(0.1, target(30.0, 20.0)));
+        assert_eq!((26.0, 16.0), camera.position());
+
+        // next to the edges the camera is clamped and the target is off-center
+        assert_eq!(Point::new(1.0, 2.0), camera.follow(0.1, target(1.0, 2.0)));
+        assert_eq!((0.0, 0.0), camera.position());
+        assert_eq!(
+            Point::new(7.0, 5.0),
+            camera.follow(0.1, target(107.0, 55.0))
+        );
+        assert_eq!((100.0, 50.0), camera.position());
+    }
+
+    #[test]
+    fn test_follow_dead_zone() {
+        let mut camera = Camera::create()
+            .width(10.0)
+            .height(10.0)
+            .max_width(100.0)
+            .max_height(100.0)
+            .dead_zone(Rectangle::new(Point::new(3.0, 3.0), 4.0, 4.0))
+            .build();
+        camera.set_position(20.0, 20.0);
+
+        // inside of the dead zone the camera does not move
+        camera.follow(0.1, target(24.0, 23.0));
+        assert_eq!((20.0, 20.0), camera.position());
+
+        // the camera moves until the target is at the edge of the dead zone
+        assert_eq!(Point::new(6.0, 2.0), camera.follow(0.1, target(30.0, 20.0)));
+        assert_eq!((24.0, 18.0), camera.position());
+    }
+
+    #[test]
+    fn test_follow_smoothing() {
+        let mut camera = Camera::create()
+            .width(10.0)
+            .height(10.0)
+            .max_width(100.0)
+            .max_height(100.0)
+            .smoothing(0.5)
+            .build();
+
+        let mut last = camera.x();
+
+        for _ in 0..20 {
+            camera.follow(0.1, target(54.0, 4.0));
+
+            // approaches the goal without overshooting
+            assert!(camera.x() > last && camera.x() <= 50.0);
+            last = camera.x();
+        }
+
+        for _ in 0..100 {
+            camera.follow(0.1, target(54.0, 4.0));
+        }
+
+        assert!((camera.x() - 50.0).abs() < 0.01);
+        assert_eq!(0.0, camera.y());
+    }
+
+    #[test]
+    fn test_follow_look_ahead() {
+        let mut camera = Camera::create()
+            .width(10.0)
+            .height(10.0)
+            .max_width(100.0)
+            .max_height(100.0)
+            .look_ahead(0.5)
+            .build();
+
+        camera.follow(0.1, target(24.0, 24.0));
+        assert_eq!((20.0, 20.0), camera.position());
+
+        // the target moves 10 per second to the right
+        camera.follow(0.1, target(25.0, 24.0));
+        assert_eq!((26.0, 20.0), camera.position());
+
+        camera.reset_follow();
+        camera.follow(0.1, target(25.0, 24.0));
+        assert_eq!((21.0, 20.0), camera.position());
+
+        // zoomed in the target is centered on the screen
+        camera.set_zoom(2.0);
+        assert_eq!(Point::new(3.0, 3.0), camera.follow(0.1, target(25.0, 24.0)));
+        assert_eq!((23.5, 22.5), camera.position());
     }
 }
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// The number of changes that are kept for observers.
+const MAX_CHANGES: usize = 4096;
+
+static NEXT_STAMP: AtomicU64 = AtomicU64::new(1);
+
+fn next_stamp() -> u64 {
+    NEXT_STAMP.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Tracks the changed tile positions of a map, e.g. for caches of renderers.
+///
+/// Each state of a map has an unique stamp. An observer remembers the stamp it has seen and asks
+/// for the changes since that stamp. If the changes are not known anymore, e.g. because the map
+/// was replaced, the whole map has to be treated as changed.
+#[derive(Clone, Debug)]
+pub struct TileChanges {
+    stamp: u64,
+
+    // the stamp before the first change of the log
+    base: u64,
+
+    // the stamp after the change, the column and the row of the changed tile
+    log: Vec<(u64, usize, usize)>,
+}
+
+impl Default for TileChanges {
+    fn default() -> Self {
+        let stamp = next_stamp();
+
+        TileChanges {
+            stamp,
+            base: stamp,
+            log: vec![],
+        }
+    }
+}
+
+// Changes do not belong to the content of a map.
+impl PartialEq for TileChanges {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl TileChanges {
+    /// Gets the stamp of the current state.
+    pub fn stamp(&self) -> u64 {
+        self.stamp
+    }
+
+    /// Records a change of the tile at the given column and row.
+    pub fn record(&mut self, column: usize, row: usize) {
+        self.stamp = next_stamp();
+        self.log.push((self.stamp, column, row));
+
+        if self.log.len() > MAX_CHANGES {
+            let dropped = self.log.len() - MAX_CHANGES / 2;
+            self.base = self.log[dropped - 1].0;
+            self.log.drain(..dropped);
+        }
+    }
+
+    /// Marks the whole map as changed.
+    pub fn record_all(&mut self) {
+        self.stamp = next_stamp();
+        self.base = self.stamp;
+        self.log.clear();
+    }
+
+    /// Gets the column and the row of each tile that has changed since the given stamp. Returns
+    /// `None` if the changes since the stamp are not known.
+    pub fn since(&self, stamp: u64) -> Option<impl Iterator<Item = (usize, usize)> + '_> {
+        let start = if stamp == self.stamp {
+            self.log.len()
+        } else if stamp == self.base {
+            0
+        } else {
+            self.log.iter().position(|c| c.0 == stamp)? + 1
+        };
+
+        Some(self.log[start..].iter().map(|c| (c.1, c.2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_since() {
+        let mut changes = TileChanges::default();
+        let start = changes.stamp();
+        assert_eq!(0, changes.since(start).unwrap().count());
+
+        changes.record(1, 2);
+        let first = changes.stamp();
+        changes.record(3, 4);
+
+        assert_eq!(
+            vec![(1, 2), (3, 4)],
+            changes.since(start).unwrap().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(3, 4)],
+            changes.since(first).unwrap().collect::<Vec<_>>()
+        );
+        assert!(changes.since(TileChanges::default().stamp()).is_none());
+
+        changes.record_all();
+        assert!(changes.since(first).is_none());
+
+        for i in 0..5000 {
+            changes.record(i, 0);
+        }
+
+        let last = changes.stamp();
+        changes.record(0, 1);
+
+        // the oldest changes are dropped
+        assert!(changes.since(first).is_none());
+        assert_eq!(1, changes.since(last).unwrap().count());
+    }
+}
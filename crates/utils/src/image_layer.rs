@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Describes a layer of a map that shows a single image, e.g. a sky or mountains behind the tiles.
+///
+/// The image is placed at the offset in pixels of the map and moves by the parallax factor of the
+/// camera movement. It can be repeated in x and y independently. Missing fields are filled with
+/// their defaults on deserialization.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageLayer {
+    /// The name of the layer.
+    pub name: String,
+
+    /// The path of the image.
+    pub image: String,
+
+    /// If `false` the layer is not drawn.
+    pub visible: bool,
+
+    /// The opacity of the layer from 0.0 (transparent) to 1.0 (opaque).
+    pub opacity: f32,
+
+    /// The position of the image in pixels of the map.
+    pub offset: (f64, f64),
+
+    /// The factor of the camera movement in x and y the layer follows. 0.0 keeps the image fixed
+    /// on the screen.
+    pub parallax: (f64, f64),
+
+    /// If `true` the image is repeated horizontally.
+    pub repeat_x: bool,
+
+    /// If `true` the image is repeated vertically.
+    pub repeat_y: bool,
+
+    /// Free-form properties of the layer.
+    pub properties: BTreeMap<String, String>,
+}
+
+impl Default for ImageLayer {
+    fn default() -> Self {
+        ImageLayer {
+            name: String::default(),
+            image: String::default(),
+            visible: true,
+            opacity: 1.0,
+            offset: (0.0, 0.0),
+            parallax: (1.0, 1.0),
+            repeat_x: false,
+            repeat_y: false,
+            properties: BTreeMap::new(),
+        }
+    }
+}
+
+impl ImageLayer {
+    /// Creates a new visible image layer with the given name and image path.
+    pub fn new(name: impl Into<String>, image: impl Into<String>) -> Self {
+        ImageLayer {
+            name: name.into(),
+            image: image.into(),
+            ..ImageLayer::default()
+        }
+    }
+
+    /// Gets the property with the given key.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+}
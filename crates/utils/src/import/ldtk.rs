@@ -0,0 +1,485 @@
+//! Importer for projects of the [LDtk](https://ldtk.io) level designer.
+//!
+//! Every level of a project is converted into its own map. Tile and auto-layer tiles become
+//! layers of the map, IntGrid values could be converted into blocked tiles with `LdtkOptions`.
+
+use std::{collections::HashMap, path::Path};
+
+use serde_derive::Deserialize;
+use serde_json::from_str;
+
+use super::{json_error, read_file, Imported};
+use crate::{Layer, Map, MapError, Tile, TileSet};
+
+/// Options of the LDtk import.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct LdtkOptions {
+    /// Maps IntGrid values to tile ids. Cells with a mapped value are filled with the tile id in
+    /// an additional layer and the tile id is added to the blocked tiles of the map.
+    pub blocked: HashMap<i32, i32>,
+}
+
+impl LdtkOptions {
+    /// Creates options with an empty IntGrid mapping.
+    pub fn new() -> Self {
+        LdtkOptions::default()
+    }
+
+    /// Marks cells with the given IntGrid value as blocked by the given tile.
+    pub fn blocked(mut self, value: i32, tile: i32) -> Self {
+        self.blocked.insert(value, tile);
+        self
+    }
+}
+
+/// A level of a LDtk project.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Level {
+    /// The identifier of the level in the project.
+    pub identifier: String,
+
+    /// The tiles of the level.
+    pub map: Map,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LdtkProject {
+    defs: LdtkDefinitions,
+    levels: Vec<LdtkLevel>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LdtkDefinitions {
+    tilesets: Vec<LdtkTileSet>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct LdtkTileSet {
+    uid: i64,
+    identifier: String,
+    rel_path: Option<String>,
+    px_wid: u32,
+    px_hei: u32,
+    tile_grid_size: u32,
+    spacing: u32,
+    padding: u32,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct LdtkLevel {
+    identifier: String,
+    external_rel_path: Option<String>,
+    layer_instances: Option<Vec<LdtkLayer>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct LdtkLayer {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    kind: String,
+    #[serde(rename = "__cWid")]
+    column_count: usize,
+    #[serde(rename = "__cHei")]
+    row_count: usize,
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    #[serde(rename = "__tilesetDefUid")]
+    tile_set: Option<i64>,
+    #[serde(rename = "__opacity")]
+    opacity: Option<f32>,
+    visible: Option<bool>,
+    int_grid_csv: Vec<i32>,
+    auto_layer_tiles: Vec<LdtkTile>,
+    grid_tiles: Vec<LdtkTile>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LdtkTile {
+    px: [i64; 2],
+    f: u8,
+    t: i32,
+}
+
+/// Loads all levels of the LDtk project at the given path. Levels saved in separate files and the
+/// images of the tile sets are resolved relative to the directory of the project.
+pub fn load<P: AsRef<Path>>(
+    path: P,
+    options: &LdtkOptions,
+) -> Result<Imported<Vec<Level>>, MapError> {
+    let path = path.as_ref();
+    let project = from_str(read_file(path)?.as_str()).map_err(json_error)?;
+
+    convert(project, options, path.parent())
+}
+
+/// Imports all levels of a LDtk project from its JSON content. Levels saved in separate files
+/// are skipped.
+pub fn from_ldtk_str(s: &str, options: &LdtkOptions) -> Result<Imported<Vec<Level>>, MapError> {
+    convert(from_str(s).map_err(json_error)?, options, None)
+}
+
+fn convert(
+    project: LdtkProject,
+    options: &LdtkOptions,
+    base: Option<&Path>,
+) -> Result<Imported<Vec<Level>>, MapError> {
+    let mut warnings = vec![];
+    let mut levels = vec![];
+    let tile_sets = convert_tile_sets(&project.defs.tilesets, base);
+
+    for mut level in project.levels {
+        if level.layer_instances.is_none() {
+            match (&level.external_rel_path, base) {
+                (Some(external), Some(base)) => {
+                    let external: LdtkLevel =
+                        from_str(read_file(&base.join(external))?.as_str()).map_err(json_error)?;
+                    level.layer_instances = external.layer_instances;
+                }
+                (Some(external), None) => {
+                    warnings.push(format!(
+                        "level {} is saved in {} and is not loaded",
+                        level.identifier, external
+                    ));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(map) = convert_level(&level, &tile_sets, options, &mut warnings)? {
+            levels.push(Level {
+                identifier: level.identifier,
+                map,
+            });
+        }
+    }
+
+    Ok(Imported {
+        value: levels,
+        warnings,
+    })
+}
+
+// Converts the tile set definitions of the project. The ids of the tile sets are placed one after
+// another in the order of the definitions. The image paths are relative to the project, so they
+// are resolved against its directory if it is known.
+fn convert_tile_sets(definitions: &[LdtkTileSet], base: Option<&Path>) -> Vec<(i64, TileSet)> {
+    let mut first_id = 0;
+    let mut tile_sets = vec![];
+
+    for definition in definitions {
+        let image = match (&definition.rel_path, base) {
+            (Some(path), Some(base)) => base.join(path).to_string_lossy().to_string(),
+            (Some(path), None) => path.clone(),
+            (None, _) => String::new(),
+        };
+
+        let mut tile_set =
+            TileSet::new(image, definition.tile_grid_size, definition.tile_grid_size);
+        tile_set.name = definition.identifier.clone();
+        tile_set.first_id = first_id;
+        tile_set.margin = definition.padding;
+        tile_set.spacing = definition.spacing;
+
+        // the tiles are square, so the rows are counted like the columns
+        let columns = tile_set.columns(definition.px_wid);
+        let rows = tile_set.columns(definition.px_hei);
+        tile_set.columns = columns;
+        first_id += columns * rows;
+
+        tile_sets.push((definition.uid, tile_set));
+    }
+
+    tile_sets
+}
+
+fn convert_level(
+    level: &LdtkLevel,
+    tile_sets: &[(i64, TileSet)],
+    options: &LdtkOptions,
+    warnings: &mut Vec<String>,
+) -> Result<Option<Map>, MapError> {
+    let instances = match &level.layer_instances {
+        Some(instances) if !instances.is_empty() => instances,
+        _ => {
+            warnings.push(format!("level {} has no layers", level.identifier));
+            return Ok(None);
+        }
+    };
+
+    // all layers of a level share the grid of the first layer
+    let grid = &instances[0];
+
+    if grid.grid_size == 0 {
+        return Err(MapError::Decode(format!(
+            "level {} has no grid size",
+            level.identifier
+        )));
+    }
+
+    let tile_count = grid.column_count * grid.row_count;
+    let mut layers = vec![];
+    let mut blocked_tiles = vec![];
+
+    // LDtk lists the top most layer first
+    for instance in instances.iter().rev() {
+        if instance.grid_size != grid.grid_size
+            || instance.column_count != grid.column_count
+            || instance.row_count != grid.row_count
+        {
+            warnings.push(format!(
+                "layer {} of level {} does not match the level grid and is skipped",
+                instance.identifier, level.identifier
+            ));
+            continue;
+        }
+
+        if instance.kind == "Entities" {
+            warnings.push(format!(
+                "entity layer {} of level {} is not supported",
+                instance.identifier, level.identifier
+            ));
+            continue;
+        }
+
+        // ids of the layer are relative to its tile set
+        let first_id = tile_sets
+            .iter()
+            .find(|(uid, _)| Some(*uid) == instance.tile_set)
+            .map_or(0, |(_, t)| t.first_id as i32);
+
+        for tiles in &[&instance.auto_layer_tiles, &instance.grid_tiles] {
+            if tiles.is_empty() {
+                continue;
+            }
+
+            let mut layer = Layer::new(instance.identifier.as_str(), vec![-1; tile_count]);
+            layer.visible = instance.visible.unwrap_or(true);
+            layer.opacity = instance.opacity.unwrap_or(1.0);
+
+            for tile in tiles.iter() {
+                let column = tile.px[0] / grid.grid_size as i64;
+                let row = tile.px[1] / grid.grid_size as i64;
+
+                if column < 0
+                    || row < 0
+                    || column as usize >= grid.column_count
+                    || row as usize >= grid.row_count
+                {
+                    continue;
+                }
+
+                // the first bit flips horizontally and the second bit vertically
+                let tile = Tile {
+                    flip_x: tile.f & 1 != 0,
+                    flip_y: tile.f & 2 != 0,
+                    ..Tile::new(first_id + tile.t)
+                };
+
+                layer.set_tile(
+                    row as usize * grid.column_count + column as usize,
+                    tile.to_entry(),
+                );
+            }
+
+            layers.push(layer);
+        }
+
+        if !options.blocked.is_empty() && !instance.int_grid_csv.is_empty() {
+            // the blocked tiles are only used for collision and are not drawn
+            let mut layer = Layer::new(format!("{}_blocked", instance.identifier), vec![]);
+            layer.visible = false;
+
+            for value in &instance.int_grid_csv {
+                let tile = options.blocked.get(value).cloned().unwrap_or(-1);
+
+                if tile != -1 && !blocked_tiles.contains(&tile) {
+                    blocked_tiles.push(tile);
+                }
+
+                layer.push(tile);
+            }
+
+            layers.push(layer);
+        }
+    }
+
+    let map = Map {
+        layer_count: layers.len(),
+        row_count: grid.row_count,
+        column_count: grid.column_count,
+        tile_size: grid.grid_size,
+        blocked_tiles,
+        layers,
+        tile_sets: tile_sets.iter().map(|(_, t)| t.clone()).collect(),
+        ..Map::default()
+    };
+
+    warnings.extend(map.check()?.iter().map(ToString::to_string));
+
+    Ok(Some(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::FLIP_X;
+
+    const PROJECT: &str = r#"{
+        "jsonVersion": "1.0.0",
+        "defs": {
+            "tilesets": [
+                {
+                    "uid": 1,
+                    "identifier": "Walls",
+                    "relPath": "walls.png",
+                    "pxWid": 64,
+                    "pxHei": 32,
+                    "tileGridSize": 16,
+                    "spacing": 0,
+                    "padding": 0
+                },
+                {
+                    "uid": 2,
+                    "identifier": "Decoration",
+                    "relPath": "decoration.png",
+                    "pxWid": 32,
+                    "pxHei": 32,
+                    "tileGridSize": 16,
+                    "spacing": 0,
+                    "padding": 0
+                }
+            ]
+        },
+        "levels": [
+            {
+                "identifier": "Level_0",
+                "layerInstances": [
+                    {
+                        "__identifier": "Entities",
+                        "__type": "Entities",
+                        "__cWid": 3,
+                        "__cHei": 2,
+                        "__gridSize": 16,
+                        "entityInstances": []
+                    },
+                    {
+                        "__identifier": "Decoration",
+                        "__type": "Tiles",
+                        "__cWid": 3,
+                        "__cHei": 2,
+                        "__gridSize": 16,
+                        "__tilesetDefUid": 2,
+                        "__opacity": 0.5,
+                        "visible": true,
+                        "gridTiles": [{ "px": [32, 16], "src": [0, 0], "f": 0, "t": 7 }]
+                    },
+                    {
+                        "__identifier": "Walls",
+                        "__type": "IntGrid",
+                        "__cWid": 3,
+                        "__cHei": 2,
+                        "__gridSize": 16,
+                        "__tilesetDefUid": 1,
+                        "intGridCsv": [1, 0, 0, 2, 0, 1],
+                        "autoLayerTiles": [
+                            { "px": [0, 0], "src": [0, 0], "f": 0, "t": 3 },
+                            { "px": [0, 16], "src": [0, 0], "f": 1, "t": 4 },
+                            { "px": [32, 16], "src": [0, 0], "f": 0, "t": 3 }
+                        ]
+                    }
+                ]
+            },
+            {
+                "identifier": "Level_1",
+                "externalRelPath": "project/Level_1.ldtkl",
+                "layerInstances": null
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_from_ldtk_str() {
+        let imported = from_ldtk_str(PROJECT, &LdtkOptions::new().blocked(1, 99)).unwrap();
+        assert_eq!(1, imported.value.len());
+
+        // entity layer and external level
+        assert_eq!(2, imported.warnings.len());
+
+        let level = &imported.value[0];
+        assert_eq!("Level_0", level.identifier);
+
+        let map = &level.map;
+        assert_eq!(3, map.layer_count());
+        assert_eq!(3, map.column_count());
+        assert_eq!(2, map.row_count());
+        assert_eq!(16, map.tile_size());
+        assert_eq!(
+            vec![3, -1, -1, 4 | FLIP_X as i32, -1, 3],
+            map.layers[0].tiles
+        );
+        assert_eq!(vec![99, -1, -1, -1, -1, 99], map.layers[1].tiles);
+        // the ids of the second tile set start after the 8 tiles of the first one
+        assert_eq!(vec![-1, -1, -1, -1, -1, 15], map.layers[2].tiles);
+        assert_eq!(2, map.tile_sets.len());
+        assert_eq!(8, map.tile_sets[1].first_id);
+        assert_eq!("decoration.png", map.tile_sets[1].image);
+        assert_eq!(Some((1, 7)), map.tile_set_index(15));
+        assert_eq!(vec![99], map.blocked_tiles);
+        assert!(!map.layer_by_name("Walls_blocked").unwrap().visible);
+        assert_eq!(0.5, map.layer_by_name("Decoration").unwrap().opacity);
+        assert!(map.is_blocked(2, 1));
+        assert!(!map.is_blocked(0, 1));
+    }
+
+    #[test]
+    fn test_from_ldtk_str_without_grid_size() {
+        let project = PROJECT.replace(r#""__gridSize": 16,"#, "");
+
+        assert!(matches!(
+            from_ldtk_str(project.as_str(), &LdtkOptions::new()),
+            Err(MapError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_image_paths() {
+        let directory = std::env::temp_dir().join(format!("orbgame_ldtk_{}", std::process::id()));
+        fs::create_dir_all(directory.join("project")).unwrap();
+        fs::write(directory.join("project.ldtk"), PROJECT).unwrap();
+        fs::write(
+            directory.join("project/Level_1.ldtkl"),
+            r#"{ "identifier": "Level_1", "layerInstances": [{ "__identifier": "Floor", "__type": "Tiles", "__cWid": 1, "__cHei": 1, "__gridSize": 16, "__tilesetDefUid": 1, "gridTiles": [{ "px": [0, 0], "f": 0, "t": 1 }] }] }"#,
+        )
+        .unwrap();
+
+        let imported = load(directory.join("project.ldtk"), &LdtkOptions::new()).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        // the images of the tile sets are relative to the directory of the project
+        assert_eq!(2, imported.value.len());
+        assert_eq!(
+            directory.join("walls.png"),
+            Path::new(&imported.value[1].map.tile_sets[0].image)
+        );
+        assert_eq!(vec![1], imported.value[1].map.layers[0].tiles);
+    }
+
+    #[test]
+    fn test_from_ldtk_str_without_mapping() {
+        let imported = from_ldtk_str(PROJECT, &LdtkOptions::default()).unwrap();
+        let map = &imported.value[0].map;
+
+        assert_eq!(2, map.layer_count());
+        assert!(map.blocked_tiles.is_empty());
+    }
+}
@@ -0,0 +1,39 @@
+use std::{fs, path::Path};
+
+use crate::MapError;
+
+pub mod ldtk;
+pub mod tiled;
+
+/// The result of an import from a foreign map format, together with warnings about unsupported
+/// features that are skipped.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Imported<T> {
+    pub value: T,
+    pub warnings: Vec<String>,
+}
+
+impl<T> Imported<T> {
+    /// Creates a new import result without warnings.
+    pub fn new(value: T) -> Self {
+        Imported {
+            value,
+            warnings: vec![],
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, MapError> {
+    fs::read_to_string(path).map_err(|e| MapError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn json_error(error: serde_json::Error) -> MapError {
+    MapError::Parse {
+        line: error.line(),
+        column: error.column(),
+        message: error.to_string(),
+    }
+}
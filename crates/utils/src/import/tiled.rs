@@ -0,0 +1,1122 @@
+//! Importer for orthogonal maps of the [Tiled](https://www.mapeditor.org) map editor.
+//!
+//! Maps could be read as XML (`.tmx`) or JSON (`.tmj`). Tile layers encoded as CSV or base64
+//! (uncompressed, zlib or gzip) are supported. Tile set tiles with a `blocked=true` property
+//! are added to the blocked tiles of the map.
+
+use std::{collections::BTreeMap, io::prelude::*, path::Path, str::FromStr};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use roxmltree::{Document, Node};
+use serde_derive::Deserialize;
+use serde_json::{from_str, Value};
+
+use super::{json_error, read_file, Imported};
+use crate::{
+    Collision, Frame, Layer, Map, MapError, MapObject, ObjectLayer, Shape, TileData, TileSet,
+    FLIP_FLAGS,
+};
+
+// Tiled stores the flipping of a tile in the four highest bits of its global id. The three highest
+// bits match the flip flags of a tile entry, the fourth is only used by hexagonal maps.
+const FLIPPED_FLAGS: u32 = 0xF000_0000;
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TiledMap {
+    orientation: String,
+    width: usize,
+    height: usize,
+    tilewidth: u32,
+    tileheight: u32,
+    infinite: bool,
+    layers: Vec<TiledLayer>,
+    tilesets: Vec<TiledTileSet>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
+    data: Option<TiledData>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    visible: Option<bool>,
+    opacity: Option<f32>,
+    tintcolor: Option<String>,
+    properties: Vec<TiledProperty>,
+    objects: Vec<TiledObject>,
+    layers: Vec<TiledLayer>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TiledObject {
+    id: u32,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    class: String,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    rotation: f64,
+    gid: Option<u32>,
+    point: bool,
+    ellipse: bool,
+    polygon: Option<Vec<TiledPoint>>,
+    polyline: Option<Vec<TiledPoint>>,
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TiledPoint {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TiledData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TiledTileSet {
+    firstgid: u32,
+    source: Option<String>,
+    name: String,
+    image: String,
+    tilewidth: u32,
+    tileheight: u32,
+    margin: u32,
+    spacing: u32,
+    columns: u32,
+    tiles: Vec<TiledTile>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TiledTile {
+    id: u32,
+    #[serde(rename = "type")]
+    kind: String,
+    class: String,
+    objectgroup: Option<TiledLayer>,
+    animation: Vec<TiledFrame>,
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TiledFrame {
+    tileid: u32,
+    duration: u32,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TiledProperty {
+    name: String,
+    value: Value,
+}
+
+impl TiledProperty {
+    fn is_true(&self) -> bool {
+        match &self.value {
+            Value::Bool(value) => *value,
+            Value::String(value) => value == "true",
+            _ => false,
+        }
+    }
+
+    fn value_string(&self) -> String {
+        match &self.value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        }
+    }
+}
+
+// Parses a Tiled color in the format `#rrggbb` or `#aarrggbb`, the alpha part is ignored.
+fn parse_color(color: &str) -> Option<(u8, u8, u8)> {
+    let color = color.trim_start_matches('#');
+
+    if color.len() != 6 && color.len() != 8 {
+        return None;
+    }
+
+    let value = u32::from_str_radix(color, 16).ok()?;
+
+    Some(((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
+
+/// Loads a Tiled map from a `.tmx` or `.tmj` file. External tile sets and the images of all tile
+/// sets are resolved relative to the directory of the map.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Imported<Map>, MapError> {
+    let path = path.as_ref();
+    let contents = read_file(path)?;
+
+    let tiled = if is_xml(path) {
+        parse_tmx(contents.as_str())?
+    } else {
+        from_str(contents.as_str()).map_err(json_error)?
+    };
+
+    convert(tiled, path.parent())
+}
+
+/// Imports a Tiled map from its XML (`.tmx`) content. External tile sets are not loaded.
+pub fn from_tmx_str(s: &str) -> Result<Imported<Map>, MapError> {
+    convert(parse_tmx(s)?, None)
+}
+
+/// Imports a Tiled map from its JSON (`.tmj`) content. External tile sets are not loaded.
+pub fn from_tmj_str(s: &str) -> Result<Imported<Map>, MapError> {
+    convert(from_str(s).map_err(json_error)?, None)
+}
+
+fn is_xml(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension == "tmx" || extension == "tsx",
+        None => false,
+    }
+}
+
+fn convert(mut tiled: TiledMap, base: Option<&Path>) -> Result<Imported<Map>, MapError> {
+    let mut warnings = vec![];
+
+    if !tiled.orientation.is_empty() && tiled.orientation != "orthogonal" {
+        warnings.push(format!(
+            "orientation {} is not supported, tiles are imported as orthogonal",
+            tiled.orientation
+        ));
+    }
+
+    if tiled.tilewidth != tiled.tileheight {
+        warnings.push(format!(
+            "tile size {}x{} is not square, tile width is used",
+            tiled.tilewidth, tiled.tileheight
+        ));
+    }
+
+    if tiled.infinite {
+        warnings.push("infinite maps are not supported, chunk data is skipped".to_string());
+    }
+
+    tiled.tilesets.sort_by_key(|t| t.firstgid);
+
+    let offset = tiled.tilesets.first().map_or(1, |t| t.firstgid.max(1));
+    let mut blocked_tiles = vec![];
+    let mut tile_sets = vec![];
+
+    for tile_set in &mut tiled.tilesets {
+        if let Some(source) = tile_set.source.clone() {
+            match base {
+                Some(base) => {
+                    let firstgid = tile_set.firstgid;
+                    *tile_set = load_tile_set(&base.join(&source))?;
+                    tile_set.firstgid = firstgid;
+
+                    // the image path of an external tile set is relative to the tile set file
+                    if let Some(directory) = Path::new(&source).parent() {
+                        tile_set.image = directory
+                            .join(&tile_set.image)
+                            .to_string_lossy()
+                            .to_string();
+                    }
+                }
+                None => warnings.push(format!(
+                    "external tile set {} is not loaded, its tile properties are skipped",
+                    source
+                )),
+            }
+        }
+
+        // the image paths are relative to the map file, so they are resolved against its
+        // directory to be loadable from the working directory
+        if let Some(base) = base {
+            if !tile_set.image.is_empty() {
+                tile_set.image = base.join(&tile_set.image).to_string_lossy().to_string();
+            }
+        }
+
+        for tile in &tile_set.tiles {
+            if tile
+                .properties
+                .iter()
+                .any(|p| p.name == "blocked" && p.is_true())
+            {
+                // malformed global ids are rejected instead of overflowing
+                let id = tile_set
+                    .firstgid
+                    .checked_add(tile.id)
+                    .and_then(|gid| gid.checked_sub(offset))
+                    .filter(|id| *id <= i32::MAX as u32)
+                    .ok_or_else(|| {
+                        MapError::Decode(format!(
+                            "tile {} of tile set {} with first global id {} has no valid id",
+                            tile.id, tile_set.name, tile_set.firstgid
+                        ))
+                    })?;
+
+                blocked_tiles.push(id as i32);
+            }
+        }
+
+        tile_sets.push(convert_tile_set(tile_set, offset));
+    }
+
+    let tile_count = tiled.width * tiled.height;
+    let mut layers = vec![];
+    let mut tiled_layers = vec![];
+    let mut object_groups = vec![];
+    flatten_layers(
+        tiled.layers,
+        &mut tiled_layers,
+        &mut object_groups,
+        &mut warnings,
+    );
+
+    for tiled_layer in tiled_layers {
+        let gids = if tiled.infinite {
+            vec![0; tile_count]
+        } else {
+            decode_layer(&tiled_layer, tile_count)?
+        };
+
+        let mut layer = Layer::new(tiled_layer.name.as_str(), vec![]);
+        layer.visible = tiled_layer.visible.unwrap_or(true);
+        layer.opacity = tiled_layer.opacity.unwrap_or(1.0);
+
+        if let Some(tint) = &tiled_layer.tintcolor {
+            match parse_color(tint) {
+                Some(tint) => layer.tint = tint,
+                None => warnings.push(format!(
+                    "invalid tint color {} of layer {}",
+                    tint, tiled_layer.name
+                )),
+            }
+        }
+
+        for property in &tiled_layer.properties {
+            layer
+                .properties
+                .insert(property.name.clone(), property.value_string());
+        }
+
+        for gid in gids {
+            let id = gid & !FLIPPED_FLAGS;
+
+            layer.push(if id < offset {
+                -1
+            } else {
+                ((id - offset) | (gid & FLIP_FLAGS)) as i32
+            });
+        }
+
+        layers.push(layer);
+    }
+
+    let map = Map {
+        layer_count: layers.len(),
+        row_count: tiled.height,
+        column_count: tiled.width,
+        tile_size: tiled.tilewidth,
+        blocked_tiles,
+        layers,
+        object_layers: object_groups
+            .into_iter()
+            .map(|g| convert_object_group(g, &mut warnings))
+            .collect(),
+        tile_sets,
+        ..Map::default()
+    };
+
+    warnings.extend(map.check()?.iter().map(ToString::to_string));
+
+    Ok(Imported {
+        value: map,
+        warnings,
+    })
+}
+
+// Collects all tile and object layers, the content of group layers is inserted at the position
+// of the group.
+fn flatten_layers(
+    layers: Vec<TiledLayer>,
+    tile_layers: &mut Vec<TiledLayer>,
+    object_groups: &mut Vec<TiledLayer>,
+    warnings: &mut Vec<String>,
+) {
+    for layer in layers {
+        match layer.kind.as_str() {
+            "tilelayer" => tile_layers.push(layer),
+            "objectgroup" => object_groups.push(layer),
+            "group" => flatten_layers(layer.layers, tile_layers, object_groups, warnings),
+            kind => warnings.push(format!("{} {} is not supported", kind, layer.name)),
+        }
+    }
+}
+
+fn convert_tile_set(tile_set: &TiledTileSet, offset: u32) -> TileSet {
+    let width = tile_set.tilewidth as f64;
+    let height = tile_set.tileheight as f64;
+    let mut tiles = vec![];
+
+    for tile in &tile_set.tiles {
+        let properties: BTreeMap<String, String> = tile
+            .properties
+            .iter()
+            .map(|p| (p.name.clone(), p.value_string()))
+            .collect();
+
+        let collision = if tile
+            .properties
+            .iter()
+            .any(|p| p.name == "blocked" && p.is_true())
+        {
+            Collision::Full
+        } else {
+            // the first rectangle or polygon of the tile collision editor is used
+            tile.objectgroup
+                .iter()
+                .flat_map(|g| g.objects.iter())
+                .filter(|o| !o.point && !o.ellipse && o.polyline.is_none())
+                .map(|o| match &o.polygon {
+                    Some(points) => {
+                        Collision::Polygon(points.iter().map(|p| (o.x + p.x, o.y + p.y)).collect())
+                    }
+                    None if o.x <= 0.0
+                        && o.y <= 0.0
+                        && o.x + o.width >= width
+                        && o.y + o.height >= height =>
+                    {
+                        Collision::Full
+                    }
+                    None => Collision::Polygon(vec![
+                        (o.x, o.y),
+                        (o.x + o.width, o.y),
+                        (o.x + o.width, o.y + o.height),
+                        (o.x, o.y + o.height),
+                    ]),
+                })
+                .next()
+                .unwrap_or_default()
+        };
+
+        let terrain = if tile.kind.is_empty() {
+            tile.class.clone()
+        } else {
+            tile.kind.clone()
+        };
+
+        tiles.push(TileData {
+            id: tile.id,
+            collision,
+            terrain: if terrain.is_empty() {
+                None
+            } else {
+                Some(terrain)
+            },
+            properties,
+            animation: tile
+                .animation
+                .iter()
+                .map(|f| Frame::new(f.tileid, f.duration))
+                .collect(),
+        });
+    }
+
+    TileSet {
+        name: tile_set.name.clone(),
+        first_id: tile_set.firstgid.saturating_sub(offset),
+        image: tile_set.image.clone(),
+        tile_width: tile_set.tilewidth,
+        tile_height: tile_set.tileheight,
+        margin: tile_set.margin,
+        spacing: tile_set.spacing,
+        columns: tile_set.columns,
+        tiles,
+    }
+}
+
+fn convert_object_group(group: TiledLayer, warnings: &mut Vec<String>) -> ObjectLayer {
+    let mut layer = ObjectLayer::new(group.name, vec![]);
+    layer.visible = group.visible.unwrap_or(true);
+
+    for property in &group.properties {
+        layer
+            .properties
+            .insert(property.name.clone(), property.value_string());
+    }
+
+    for object in group.objects {
+        if object.rotation != 0.0 {
+            warnings.push(format!(
+                "rotation of object {} is not supported and is ignored",
+                object.id
+            ));
+        }
+
+        let (x, y) = (object.x, object.y);
+        let offset = |points: Vec<TiledPoint>| points.iter().map(|p| (x + p.x, y + p.y)).collect();
+
+        let shape = if object.point {
+            Shape::Point { x, y }
+        } else if object.ellipse {
+            Shape::Ellipse {
+                x,
+                y,
+                width: object.width,
+                height: object.height,
+            }
+        } else if let Some(points) = object.polygon {
+            Shape::Polygon(offset(points))
+        } else if let Some(points) = object.polyline {
+            Shape::Polyline(offset(points))
+        } else {
+            Shape::Rect {
+                x,
+                // tile objects are aligned at their bottom
+                y: if object.gid.is_some() {
+                    y - object.height
+                } else {
+                    y
+                },
+                width: object.width,
+                height: object.height,
+            }
+        };
+
+        layer.objects.push(MapObject {
+            id: object.id,
+            name: object.name,
+            kind: if object.kind.is_empty() {
+                object.class
+            } else {
+                object.kind
+            },
+            shape,
+            properties: object
+                .properties
+                .iter()
+                .map(|p| (p.name.clone(), p.value_string()))
+                .collect(),
+        });
+    }
+
+    layer
+}
+
+fn decode_layer(layer: &TiledLayer, tile_count: usize) -> Result<Vec<u32>, MapError> {
+    match &layer.data {
+        None => Ok(vec![0; tile_count]),
+        Some(TiledData::Gids(gids)) => Ok(gids.clone()),
+        Some(TiledData::Encoded(data)) => match layer.encoding.as_deref() {
+            Some("csv") => data
+                .split(',')
+                .map(|gid| gid.trim())
+                .filter(|gid| !gid.is_empty())
+                .map(|gid| {
+                    gid.parse().map_err(|_| {
+                        MapError::Decode(format!("invalid tile id {} in layer {}", gid, layer.name))
+                    })
+                })
+                .collect(),
+            Some("base64") => decode_base64(data.as_str(), layer.compression.as_deref()),
+            encoding => Err(MapError::Decode(format!(
+                "unknown encoding {} of layer {}",
+                encoding.unwrap_or("none"),
+                layer.name
+            ))),
+        },
+    }
+}
+
+fn decode_base64(data: &str, compression: Option<&str>) -> Result<Vec<u32>, MapError> {
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    let data = base64::decode(&data).map_err(|e| MapError::Decode(e.to_string()))?;
+    let mut bytes = vec![];
+
+    let result = match compression {
+        None | Some("") => {
+            bytes = data;
+            Ok(0)
+        }
+        Some("zlib") => ZlibDecoder::new(data.as_slice()).read_to_end(&mut bytes),
+        Some("gzip") => GzDecoder::new(data.as_slice()).read_to_end(&mut bytes),
+        Some(compression) => {
+            return Err(MapError::Decode(format!(
+                "compression {} is not supported",
+                compression
+            )))
+        }
+    };
+
+    result.map_err(|e| MapError::Decode(e.to_string()))?;
+
+    if bytes.len() % 4 != 0 {
+        return Err(MapError::Decode(
+            "layer data is not a sequence of 32 bit tile ids".to_string(),
+        ));
+    }
+
+    Ok(bytes
+        .chunks(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+fn load_tile_set(path: &Path) -> Result<TiledTileSet, MapError> {
+    let contents = read_file(path)?;
+
+    if is_xml(path) {
+        let document = Document::parse(contents.as_str()).map_err(xml_error)?;
+        parse_xml_tile_set(document.root_element())
+    } else {
+        from_str(contents.as_str()).map_err(json_error)
+    }
+}
+
+fn xml_error(error: roxmltree::Error) -> MapError {
+    let position = error.pos();
+
+    MapError::Parse {
+        line: position.row as usize,
+        column: position.col as usize,
+        message: error.to_string(),
+    }
+}
+
+fn attribute<T: FromStr>(node: Node, name: &str) -> Result<T, MapError> {
+    let value = node.attribute(name).ok_or_else(|| {
+        MapError::Decode(format!(
+            "missing attribute {} of {}",
+            name,
+            node.tag_name().name()
+        ))
+    })?;
+
+    value.parse().map_err(|_| {
+        MapError::Decode(format!(
+            "invalid attribute {}=\"{}\" of {}",
+            name,
+            value,
+            node.tag_name().name()
+        ))
+    })
+}
+
+fn optional_attribute<T: FromStr>(node: Node, name: &str, default: T) -> Result<T, MapError> {
+    if node.attribute(name).is_some() {
+        attribute(node, name)
+    } else {
+        Ok(default)
+    }
+}
+
+fn parse_tmx(s: &str) -> Result<TiledMap, MapError> {
+    let document = Document::parse(s).map_err(xml_error)?;
+    let root = document.root_element();
+
+    if !root.has_tag_name("map") {
+        return Err(MapError::Decode("root element is not a map".to_string()));
+    }
+
+    Ok(TiledMap {
+        orientation: root
+            .attribute("orientation")
+            .unwrap_or_default()
+            .to_string(),
+        width: attribute(root, "width")?,
+        height: attribute(root, "height")?,
+        tilewidth: attribute(root, "tilewidth")?,
+        tileheight: attribute(root, "tileheight")?,
+        infinite: optional_attribute(root, "infinite", 0)? != 0,
+        layers: parse_xml_layers(root)?,
+        tilesets: root
+            .children()
+            .filter(|n| n.has_tag_name("tileset"))
+            .map(parse_xml_tile_set)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn parse_xml_layers(node: Node) -> Result<Vec<TiledLayer>, MapError> {
+    let mut layers = vec![];
+
+    for child in node.children().filter(Node::is_element) {
+        let kind = match child.tag_name().name() {
+            "layer" => "tilelayer",
+            "group" => "group",
+            "objectgroup" => "objectgroup",
+            "imagelayer" => "imagelayer",
+            _ => continue,
+        };
+
+        let mut layer = TiledLayer {
+            kind: kind.to_string(),
+            name: child.attribute("name").unwrap_or_default().to_string(),
+            visible: Some(optional_attribute(child, "visible", 1)? != 0),
+            opacity: Some(optional_attribute(child, "opacity", 1.0)?),
+            tintcolor: child.attribute("tintcolor").map(str::to_string),
+            properties: parse_xml_properties(child),
+            ..TiledLayer::default()
+        };
+
+        match kind {
+            "tilelayer" => {
+                if let Some(data) = child.children().find(|n| n.has_tag_name("data")) {
+                    layer.encoding = data.attribute("encoding").map(str::to_string);
+                    layer.compression = data.attribute("compression").map(str::to_string);
+                    layer.data = Some(if layer.encoding.is_some() {
+                        TiledData::Encoded(data.text().unwrap_or_default().to_string())
+                    } else {
+                        TiledData::Gids(
+                            data.children()
+                                .filter(|n| n.has_tag_name("tile"))
+                                .map(|n| optional_attribute(n, "gid", 0))
+                                .collect::<Result<_, _>>()?,
+                        )
+                    });
+                }
+            }
+            "group" => layer.layers = parse_xml_layers(child)?,
+            "objectgroup" => {
+                layer.objects = child
+                    .children()
+                    .filter(|n| n.has_tag_name("object"))
+                    .map(parse_xml_object)
+                    .collect::<Result<_, _>>()?
+            }
+            _ => {}
+        }
+
+        layers.push(layer);
+    }
+
+    Ok(layers)
+}
+
+fn parse_xml_properties(node: Node) -> Vec<TiledProperty> {
+    node.children()
+        .filter(|n| n.has_tag_name("properties"))
+        .flat_map(|n| n.children())
+        .filter(|n| n.has_tag_name("property"))
+        .map(|property| TiledProperty {
+            name: property.attribute("name").unwrap_or_default().to_string(),
+            value: Value::String(
+                property
+                    .attribute("value")
+                    .or_else(|| property.text())
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+        })
+        .collect()
+}
+
+fn parse_xml_points(node: Node) -> Result<Vec<TiledPoint>, MapError> {
+    let points = node.attribute("points").unwrap_or_default();
+
+    points
+        .split_whitespace()
+        .map(|point| {
+            let mut coordinates = point.split(',').map(f64::from_str);
+
+            match (coordinates.next(), coordinates.next()) {
+                (Some(Ok(x)), Some(Ok(y))) => Ok(TiledPoint { x, y }),
+                _ => Err(MapError::Decode(format!("invalid point {}", point))),
+            }
+        })
+        .collect()
+}
+
+fn parse_xml_object(node: Node) -> Result<TiledObject, MapError> {
+    let child = |name| node.children().find(|n| n.has_tag_name(name));
+
+    Ok(TiledObject {
+        id: optional_attribute(node, "id", 0)?,
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        kind: node.attribute("type").unwrap_or_default().to_string(),
+        class: node.attribute("class").unwrap_or_default().to_string(),
+        x: optional_attribute(node, "x", 0.0)?,
+        y: optional_attribute(node, "y", 0.0)?,
+        width: optional_attribute(node, "width", 0.0)?,
+        height: optional_attribute(node, "height", 0.0)?,
+        rotation: optional_attribute(node, "rotation", 0.0)?,
+        gid: node.attribute("gid").and_then(|gid| gid.parse().ok()),
+        point: child("point").is_some(),
+        ellipse: child("ellipse").is_some(),
+        polygon: child("polygon").map(parse_xml_points).transpose()?,
+        polyline: child("polyline").map(parse_xml_points).transpose()?,
+        properties: parse_xml_properties(node),
+    })
+}
+
+fn parse_xml_tile_set(node: Node) -> Result<TiledTileSet, MapError> {
+    let mut tiles = vec![];
+
+    for tile in node.children().filter(|n| n.has_tag_name("tile")) {
+        tiles.push(TiledTile {
+            id: attribute(tile, "id")?,
+            kind: tile.attribute("type").unwrap_or_default().to_string(),
+            class: tile.attribute("class").unwrap_or_default().to_string(),
+            objectgroup: match tile.children().find(|n| n.has_tag_name("objectgroup")) {
+                Some(group) => Some(TiledLayer {
+                    objects: group
+                        .children()
+                        .filter(|n| n.has_tag_name("object"))
+                        .map(parse_xml_object)
+                        .collect::<Result<_, _>>()?,
+                    ..TiledLayer::default()
+                }),
+                None => None,
+            },
+            animation: match tile.children().find(|n| n.has_tag_name("animation")) {
+                Some(animation) => animation
+                    .children()
+                    .filter(|n| n.has_tag_name("frame"))
+                    .map(|frame| {
+                        Ok(TiledFrame {
+                            tileid: attribute(frame, "tileid")?,
+                            duration: attribute(frame, "duration")?,
+                        })
+                    })
+                    .collect::<Result<_, MapError>>()?,
+                None => vec![],
+            },
+            properties: parse_xml_properties(tile),
+        });
+    }
+
+    Ok(TiledTileSet {
+        firstgid: optional_attribute(node, "firstgid", 0)?,
+        source: node.attribute("source").map(str::to_string),
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        image: node
+            .children()
+            .find(|n| n.has_tag_name("image"))
+            .and_then(|n| n.attribute("source"))
+            .unwrap_or_default()
+            .to_string(),
+        tilewidth: optional_attribute(node, "tilewidth", 0)?,
+        tileheight: optional_attribute(node, "tileheight", 0)?,
+        margin: optional_attribute(node, "margin", 0)?,
+        spacing: optional_attribute(node, "spacing", 0)?,
+        columns: optional_attribute(node, "columns", 0)?,
+        tiles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::prelude::*};
+
+    use flate2::{
+        write::{GzEncoder, ZlibEncoder},
+        Compression,
+    };
+
+    use super::*;
+    use crate::FLIP_X;
+
+    fn encode(gids: &[u32], compression: &str) -> String {
+        let bytes: Vec<u8> = gids.iter().flat_map(|g| g.to_le_bytes().to_vec()).collect();
+
+        let bytes = match compression {
+            "zlib" => {
+                let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+                encoder.write_all(&bytes).unwrap();
+                encoder.finish().unwrap()
+            }
+            "gzip" => {
+                let mut encoder = GzEncoder::new(vec![], Compression::default());
+                encoder.write_all(&bytes).unwrap();
+                encoder.finish().unwrap()
+            }
+            _ => bytes,
+        };
+
+        base64::encode(&bytes)
+    }
+
+    fn tmx(data: &str) -> String {
+        format!(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.5" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <tileset firstgid="1" name="dungeon" tilewidth="16" tileheight="16" spacing="1" margin="2" columns="8">
+  <image source="tile_set.png" width="140" height="140"/>
+  <tile id="1" type="water">
+   <objectgroup draworder="index">
+    <object id="1" x="0" y="8" width="16" height="8"/>
+   </objectgroup>
+  </tile>
+  <tile id="2">
+   <animation>
+    <frame tileid="2" duration="500"/>
+    <frame tileid="3" duration="250"/>
+   </animation>
+   <properties>
+    <property name="blocked" type="bool" value="true"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="floor" width="2" height="2" opacity="0.75" tintcolor="#ff8000">
+  <properties>
+   <property name="kind" value="stone"/>
+  </properties>
+  <data encoding="csv">
+1,2,
+3,0
+</data>
+ </layer>
+ {}
+</map>"##,
+            data
+        )
+    }
+
+    #[test]
+    fn test_from_tmx_str_csv() {
+        let imported = from_tmx_str(tmx("").as_str()).unwrap();
+        let map = imported.value;
+
+        assert!(imported.warnings.is_empty());
+        assert_eq!(1, map.layer_count());
+        assert_eq!(16, map.tile_size());
+        assert_eq!(vec![0, 1, 2, -1], map.layers[0].tiles);
+        assert_eq!(vec![2], map.blocked_tiles);
+
+        let floor = map.layer_by_name("floor").unwrap();
+        assert!(floor.visible);
+        assert_eq!(0.75, floor.opacity);
+        assert_eq!((255, 128, 0), floor.tint);
+        assert_eq!(Some("stone"), floor.property("kind"));
+
+        let tile_set = map.tile_set(0).unwrap();
+        assert_eq!("dungeon", tile_set.name);
+        assert_eq!("tile_set.png", tile_set.image);
+        assert_eq!(
+            (16, 16, 2, 1, 8),
+            (
+                tile_set.tile_width,
+                tile_set.tile_height,
+                tile_set.margin,
+                tile_set.spacing,
+                tile_set.columns
+            )
+        );
+        assert_eq!(Some("water"), map.tile_data(1).unwrap().terrain.as_deref());
+        assert_eq!(
+            &Collision::Polygon(vec![(0.0, 8.0), (16.0, 8.0), (16.0, 16.0), (0.0, 16.0)]),
+            map.collision(1)
+        );
+        assert_eq!(&Collision::Full, map.collision(2));
+        assert_eq!(
+            vec![Frame::new(2, 500), Frame::new(3, 250)],
+            map.tile_data(2).unwrap().animation
+        );
+        assert_eq!(3, map.animated_tile(2, 600.0));
+        assert!(map.is_tile_blocked(20.0, 12.0));
+        assert!(!map.is_tile_blocked(20.0, 4.0));
+    }
+
+    #[test]
+    fn test_from_tmx_str_base64() {
+        for compression in &["", "zlib", "gzip"] {
+            let layer = format!(
+                r#"<layer id="2" name="walls" width="2" height="2">
+  <data encoding="base64" compression="{}">
+   {}
+  </data>
+ </layer>"#,
+                compression,
+                encode(&[4, 0, 0, 3], compression)
+            );
+
+            let map = from_tmx_str(tmx(layer.as_str()).as_str()).unwrap().value;
+            assert_eq!(2, map.layer_count());
+            assert_eq!(vec![3, -1, -1, 2], map.layers[1].tiles);
+        }
+    }
+
+    #[test]
+    fn test_from_tmx_str_warnings() {
+        let layer = r#"<imagelayer id="4" name="sky"/>"#;
+
+        let imported =
+            from_tmx_str(tmx(layer).replace("orthogonal", "isometric").as_str()).unwrap();
+
+        assert_eq!(2, imported.warnings.len());
+        assert_eq!(1, imported.value.layer_count());
+    }
+
+    #[test]
+    fn test_from_tmx_str_objects() {
+        let layer = r#"<objectgroup id="3" name="entities">
+  <object id="1" name="player" type="spawn" x="8" y="8">
+   <point/>
+  </object>
+  <object id="2" name="exit" class="door" x="0" y="0" width="16" height="16">
+   <properties>
+    <property name="target" value="level_2"/>
+   </properties>
+  </object>
+  <object id="3" name="pond" x="32" y="0" width="16" height="8">
+   <ellipse/>
+  </object>
+  <object id="4" name="patrol" type="path" x="10" y="10">
+   <polyline points="0,0 16,0 16,16"/>
+  </object>
+  <object id="5" name="chest" gid="3" x="0" y="32" width="16" height="16"/>
+ </objectgroup>"#;
+
+        let imported = from_tmx_str(tmx(layer).as_str()).unwrap();
+        let map = imported.value;
+
+        assert!(imported.warnings.is_empty());
+        assert_eq!(5, map.objects().count());
+        assert_eq!(
+            Shape::Point { x: 8.0, y: 8.0 },
+            map.object_by_name("player").unwrap().shape
+        );
+        assert_eq!("door", map.object_by_name("exit").unwrap().kind);
+        assert_eq!(
+            Some("level_2"),
+            map.object_by_name("exit").unwrap().property("target")
+        );
+        assert_eq!(
+            Shape::Polyline(vec![(10.0, 10.0), (26.0, 10.0), (26.0, 26.0)]),
+            map.object_by_name("patrol").unwrap().shape
+        );
+        assert_eq!(
+            Shape::Rect {
+                x: 0.0,
+                y: 16.0,
+                width: 16.0,
+                height: 16.0
+            },
+            map.object_by_name("chest").unwrap().shape
+        );
+        assert!(map.objects_at(40.0, 4.0).any(|o| o.name == "pond"));
+    }
+
+    #[test]
+    fn test_from_tmj_str() {
+        let tmj = format!(
+            r#"{{
+                "orientation": "orthogonal",
+                "width": 2,
+                "height": 1,
+                "tilewidth": 8,
+                "tileheight": 8,
+                "infinite": false,
+                "tilesets": [
+                    {{ "firstgid": 11, "tiles": [{{ "id": 1, "animation": [{{ "tileid": 0, "duration": 100 }}, {{ "tileid": 1, "duration": 100 }}], "properties": [{{ "name": "blocked", "type": "bool", "value": true }}] }}] }},
+                    {{ "firstgid": 20, "name": "props", "image": "props.png", "tilewidth": 8, "tileheight": 8 }}
+                ],
+                "layers": [
+                    {{ "type": "tilelayer", "name": "floor", "data": [11, 12] }},
+                    {{ "type": "tilelayer", "name": "props", "data": [0, 21] }},
+                    {{ "type": "objectgroup", "name": "regions", "objects": [
+                        {{ "id": 1, "name": "lake", "type": "water", "x": 0, "y": 0, "polygon": [{{ "x": 0, "y": 0 }}, {{ "x": 8, "y": 0 }}, {{ "x": 0, "y": 8 }}] }}
+                    ] }},
+                    {{ "type": "group", "name": "top", "layers": [
+                        {{ "type": "tilelayer", "name": "walls", "visible": false, "encoding": "base64", "compression": "zlib", "data": "{}" }}
+                    ] }}
+                ]
+            }}"#,
+            encode(&[0, 12 | 0x8000_0000], "zlib")
+        );
+
+        let imported = from_tmj_str(tmj.as_str()).unwrap();
+        let map = imported.value;
+
+        assert!(imported.warnings.is_empty());
+        assert_eq!(3, map.layer_count());
+        assert_eq!(vec![0, 1], map.layers[0].tiles);
+        assert_eq!(vec![-1, 10], map.layers[1].tiles);
+        assert_eq!(vec![-1, 1 | FLIP_X as i32], map.layers[2].tiles);
+        assert!(map.get_tile_with_flags(2, 0, 1).flip_x);
+        assert!(!map.layers[2].visible);
+        assert_eq!(Some((1, 1)), map.tile_set_index(10));
+        assert_eq!(9, map.tile_sets[1].first_id);
+        assert_eq!(
+            Shape::Polygon(vec![(0.0, 0.0), (8.0, 0.0), (0.0, 8.0)]),
+            map.object_layer_by_name("regions").unwrap().objects[0].shape
+        );
+        assert_eq!(vec![1], map.blocked_tiles);
+        assert_eq!(0, map.animated_tile(1, 50.0));
+    }
+
+    #[test]
+    fn test_load_image_paths() {
+        let directory = std::env::temp_dir().join(format!("orbgame_tiled_{}", std::process::id()));
+        fs::create_dir_all(directory.join("sets")).unwrap();
+        fs::write(
+            directory.join("sets/props.tsx"),
+            r#"<tileset name="props" tilewidth="16" tileheight="16" columns="2">
+  <image source="props.png" width="32" height="16"/>
+</tileset>"#,
+        )
+        .unwrap();
+        fs::write(
+            directory.join("level.tmx"),
+            tmx("").replace(
+                "</map>",
+                r#" <tileset firstgid="100" source="sets/props.tsx"/>
+</map>"#,
+            ),
+        )
+        .unwrap();
+
+        let map = load(directory.join("level.tmx")).unwrap().value;
+        fs::remove_dir_all(&directory).unwrap();
+
+        // embedded and external tile sets are relative to the directory of the map
+        assert_eq!(
+            directory.join("tile_set.png"),
+            Path::new(&map.tile_sets[0].image)
+        );
+        assert_eq!(
+            directory.join("sets").join("props.png"),
+            Path::new(&map.tile_sets[1].image)
+        );
+    }
+
+    #[test]
+    fn test_from_tmx_str_errors() {
+        assert!(matches!(from_tmx_str("<map"), Err(MapError::Parse { .. })));
+        assert!(matches!(
+            from_tmx_str(tmx("").replace("3,0", "3").as_str()),
+            Err(MapError::Structure(_))
+        ));
+
+        // global ids of blocked tiles below the first tile set or beyond the id range
+        assert!(matches!(
+            from_tmx_str(
+                tmx("")
+                    .replace("firstgid=\"1\"", "firstgid=\"0\"")
+                    .replace("<tile id=\"2\">", "<tile id=\"0\">")
+                    .as_str()
+            ),
+            Err(MapError::Decode(_))
+        ));
+        assert!(matches!(
+            from_tmx_str(
+                tmx("")
+                    .replace("<tile id=\"2\">", "<tile id=\"4294967295\">")
+                    .as_str()
+            ),
+            Err(MapError::Decode(_))
+        ));
+    }
+}
@@ -1,9 +1,29 @@
+pub use self::animation::*;
+pub use self::changes::*;
+pub use self::image_layer::*;
+pub use self::import::*;
 pub use self::map::*;
+pub use self::object::*;
+pub use self::path::*;
+pub use self::shake::*;
+pub use self::shared_map::*;
+pub use self::tile::*;
+pub use self::tile_set::*;
 pub use camera::*;
 
 pub use orbtk::utils;
 
 pub mod prelude;
 
+mod animation;
 mod camera;
+mod changes;
+mod image_layer;
+mod import;
 mod map;
+mod object;
+mod path;
+mod shake;
+mod shared_map;
+mod tile;
+mod tile_set;
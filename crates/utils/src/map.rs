@@ -1,19 +1,236 @@
-use std::{fs::File, io::prelude::*};
+use std::{
+    cmp, collections::BTreeMap, convert::TryFrom, error::Error, fmt, fs, ops::Range, path::Path,
+};
 
 use ron::{
-    de::from_str,
+    de::{self, from_str},
     ser::{to_string_pretty, PrettyConfig},
 };
 use serde_derive::{Deserialize, Serialize};
 
 use orbtk::prelude::*;
 
-#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+use crate::{
+    path, tile_id, Camera, Collision, ImageLayer, MapObject, ObjectLayer, PathOptions, Tile,
+    TileChanges, TileData, TileSet,
+};
+
+/// Describes the errors that could occur on loading or saving a map.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MapError {
+    /// The map file could not be read or written.
+    Io { path: String, message: String },
+
+    /// The RON content of the map could not be parsed. Line and column are one-based.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    /// The map could not be serialized to RON.
+    Serialize(String),
+
+    /// The content of a foreign map format could not be decoded.
+    Decode(String),
+
+    /// The map violates one or more of its structural invariants.
+    Structure(Vec<ValidationError>),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io { path, message } => write!(f, "could not access {}: {}", path, message),
+            MapError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}: {}", line, column, message),
+            MapError::Serialize(message) => write!(f, "could not serialize map: {}", message),
+            MapError::Decode(message) => write!(f, "could not decode map: {}", message),
+            MapError::Structure(errors) => {
+                write!(f, "invalid map structure")?;
+
+                for (i, error) in errors.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { "," }, error)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for MapError {}
+
+impl From<de::Error> for MapError {
+    fn from(error: de::Error) -> Self {
+        match error {
+            de::Error::Parser(kind, position) => MapError::Parse {
+                line: position.line,
+                column: position.col,
+                message: format!("{:?}", kind),
+            },
+            de::Error::IoError(message) | de::Error::Message(message) => MapError::Parse {
+                line: 0,
+                column: 0,
+                message,
+            },
+        }
+    }
+}
+
+/// Describes a violation of the structural invariants of a map.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// The number of layers differs from `layer_count`.
+    LayerCount { expected: usize, found: usize },
+
+    /// The layer does not hold `row_count * column_count` tiles.
+    TileCount {
+        layer: usize,
+        expected: usize,
+        found: usize,
+    },
+
+    /// The tile entry is below -1 (empty) and is no flipped tile.
+    InvalidTile {
+        layer: usize,
+        index: usize,
+        tile: i32,
+    },
+
+    /// The `tile_size` of the map is zero.
+    ZeroTileSize,
+
+    /// The blocked tile id is not used by any layer. This is a warning, the tile could be placed
+    /// later.
+    UnusedBlockedTile(i32),
+}
+
+impl ValidationError {
+    /// Returns `true` if the violation is only a warning that does not fail loading a map.
+    pub fn is_warning(&self) -> bool {
+        matches!(self, ValidationError::UnusedBlockedTile(_))
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::LayerCount { expected, found } => {
+                write!(f, "expected {} layers, found {}", expected, found)
+            }
+            ValidationError::TileCount {
+                layer,
+                expected,
+                found,
+            } => write!(
+                f,
+                "layer {} expected {} tiles, found {}",
+                layer, expected, found
+            ),
+            ValidationError::InvalidTile { layer, index, tile } => write!(
+                f,
+                "layer {} has invalid tile id {} at index {}",
+                layer, tile, index
+            ),
+            ValidationError::ZeroTileSize => write!(f, "tile size is zero"),
+            ValidationError::UnusedBlockedTile(tile) => {
+                write!(f, "blocked tile id {} is not used", tile)
+            }
+        }
+    }
+}
+
+/// Defines how a map is validated after it is read.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Validation {
+    /// The map is returned without any checks.
+    Skip,
+
+    /// Every violation that is no warning is reported as `MapError::Structure`.
+    Check,
+
+    /// Fixable violations are repaired (see `Map::repair`), the remaining ones are reported.
+    Repair,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Validation::Check
+    }
+}
+
+/// Describes a layer of tiles of a map.
+///
+/// Besides its tiles a layer has a name, a visibility, an opacity, a tint, a parallax factor and
+/// free-form properties. Missing fields are filled with their defaults on deserialization.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Layer {
+    /// The name of the layer.
+    pub name: String,
+
+    /// If `false` the layer is not drawn.
+    pub visible: bool,
+
+    /// The opacity of the layer from 0.0 (transparent) to 1.0 (opaque).
+    pub opacity: f32,
+
+    /// The red, green and blue tint that is multiplied with the tiles of the layer.
+    pub tint: (u8, u8, u8),
+
+    /// The factor of the camera movement in x and y the layer follows. Layers with a factor below
+    /// 1.0 scroll slower than the map like a distant background.
+    pub parallax: (f64, f64),
+
+    /// Free-form properties of the layer.
+    pub properties: BTreeMap<String, String>,
+
     pub tiles: Vec<i32>,
 }
 
+impl Default for Layer {
+    fn default() -> Self {
+        Layer {
+            name: String::default(),
+            visible: true,
+            opacity: 1.0,
+            tint: (255, 255, 255),
+            parallax: (1.0, 1.0),
+            properties: BTreeMap::new(),
+            tiles: vec![],
+        }
+    }
+}
+
 impl Layer {
+    /// Creates a new visible layer with the given name and tiles.
+    pub fn new(name: impl Into<String>, tiles: Vec<i32>) -> Self {
+        Layer {
+            name: name.into(),
+            tiles,
+            ..Layer::default()
+        }
+    }
+
+    /// Returns `true` if the layer is not tinted.
+    pub fn is_untinted(&self) -> bool {
+        self.tint == (255, 255, 255)
+    }
+
+    /// Returns `true` if the layer moves by another factor than the camera.
+    pub fn has_parallax(&self) -> bool {
+        self.parallax != (1.0, 1.0)
+    }
+
+    /// Gets the property with the given key.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
     pub fn push(&mut self, tile: i32) {
         self.tiles.push(tile);
     }
@@ -25,6 +242,8 @@ impl Layer {
         self.tiles.remove(index)
     }
 
+    /// Sets the tile at the given index. The change is not tracked, use `Map::set_tile` for maps
+    /// that are drawn.
     pub fn set_tile(&mut self, index: usize, tile: i32) {
         if let Some(t) = self.tiles.get_mut(index) {
             *t = tile;
@@ -40,17 +259,267 @@ pub struct Map {
     pub tile_size: u32,
     pub blocked_tiles: Vec<i32>,
     pub layers: Vec<Layer>,
+
+    /// Layers of images that are drawn behind the tile layers, from the first to the last.
+    #[serde(default)]
+    pub image_layers: Vec<ImageLayer>,
+
+    /// Layers of objects like spawn points, triggers and regions.
+    #[serde(default)]
+    pub object_layers: Vec<ObjectLayer>,
+
+    /// The tile sets used to draw the tiles and to look up tile data like collision shapes. Each
+    /// tile set covers the tile ids from its `first_id` up to the `first_id` of the next one.
+    #[serde(default)]
+    pub tile_sets: Vec<TileSet>,
+
+    /// Tracks the tiles changed by `set_tile` and the changes by the other setters of the map.
+    /// After changes of the public fields, e.g. of a layer, `mark_changed` has to be called.
+    #[serde(skip)]
+    pub changes: TileChanges,
 }
 
-into_property_source!(Map: &str, String);
+into_property_source!(Map);
 
 impl Map {
+    /// Loads a map from the RON file at the given path and checks its structure.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Map, MapError> {
+        Map::load_with(path, Validation::Check)
+    }
+
+    /// Loads a map from the RON file at the given path using the given validation.
+    pub fn load_with<P: AsRef<Path>>(path: P, validation: Validation) -> Result<Map, MapError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| MapError::Io {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+
+        Map::from_ron_str_with(contents.as_str(), validation)
+    }
+
+    /// Reads a map from a RON string and checks its structure.
+    pub fn from_ron_str(s: &str) -> Result<Map, MapError> {
+        Map::from_ron_str_with(s, Validation::Check)
+    }
+
+    /// Reads a map from a RON string using the given validation.
+    pub fn from_ron_str_with(s: &str, validation: Validation) -> Result<Map, MapError> {
+        let mut map: Map = from_str(s)?;
+
+        if validation == Validation::Skip {
+            return Ok(map);
+        }
+
+        if validation == Validation::Repair {
+            map.repair();
+        }
+
+        map.check()?;
+
+        Ok(map)
+    }
+
+    /// Checks the structural invariants of the map and returns every violation.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = vec![];
+
+        if self.layers.len() != self.layer_count {
+            errors.push(ValidationError::LayerCount {
+                expected: self.layer_count,
+                found: self.layers.len(),
+            });
+        }
+
+        if self.tile_size == 0 {
+            errors.push(ValidationError::ZeroTileSize);
+        }
+
+        let tile_count = self.row_count * self.column_count;
+
+        for (l, layer) in self.layers.iter().enumerate() {
+            if layer.tiles.len() != tile_count {
+                errors.push(ValidationError::TileCount {
+                    layer: l,
+                    expected: tile_count,
+                    found: layer.tiles.len(),
+                });
+            }
+
+            for (index, tile) in layer.tiles.iter().enumerate() {
+                if !is_valid_entry(*tile) {
+                    errors.push(ValidationError::InvalidTile {
+                        layer: l,
+                        index,
+                        tile: *tile,
+                    });
+                }
+            }
+        }
+
+        for blocked in &self.blocked_tiles {
+            if !self.layers.iter().any(|l| contains_tile(l, *blocked)) {
+                errors.push(ValidationError::UnusedBlockedTile(*blocked));
+            }
+        }
+
+        errors
+    }
+
+    /// Checks the structural invariants of the map like `validate`. Returns the warnings if there
+    /// are no other violations.
+    pub fn check(&self) -> Result<Vec<ValidationError>, MapError> {
+        let (warnings, errors): (Vec<_>, Vec<_>) =
+            self.validate().into_iter().partition(|e| e.is_warning());
+
+        if !errors.is_empty() {
+            return Err(MapError::Structure(errors));
+        }
+
+        Ok(warnings)
+    }
+
+    /// Repairs the fixable violations of the map: missing layers and tiles are padded with -1,
+    /// surplus layers and tiles are removed and invalid tile ids are replaced by -1. Blocked tile
+    /// ids are kept, even if they are not used by the map yet.
+    pub fn repair(&mut self) {
+        self.changes.record_all();
+
+        let tile_count = self.row_count * self.column_count;
+
+        self.layers.resize(self.layer_count, Layer::default());
+
+        for layer in &mut self.layers {
+            layer.tiles.resize(tile_count, -1);
+
+            for tile in &mut layer.tiles {
+                if !is_valid_entry(*tile) {
+                    *tile = -1;
+                }
+            }
+        }
+    }
+
+    /// Serializes the map to a pretty printed RON string.
+    pub fn to_ron_string(&self) -> Result<String, MapError> {
+        to_string_pretty(self, PrettyConfig::default())
+            .map_err(|e| MapError::Serialize(e.to_string()))
+    }
+
+    /// Saves the map as RON file to the given path.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), MapError> {
+        let path = path.as_ref();
+
+        fs::write(path, self.to_ron_string()?).map_err(|e| MapError::Io {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })
+    }
+
     pub fn layer_count(&self) -> usize {
         self.layer_count
     }
 
+    /// Gets the first layer with the given name.
+    pub fn layer_by_name(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+
+    /// Gets the first layer with the given name as mutable reference. Changes of the layer are not
+    /// tracked, use the setters of the map or `mark_changed` for maps that are drawn.
+    pub fn layer_by_name_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|l| l.name == name)
+    }
+
+    /// Gets the index of the first layer with the given name.
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|l| l.name == name)
+    }
+
+    /// Gets the first object layer with the given name.
+    pub fn object_layer_by_name(&self, name: &str) -> Option<&ObjectLayer> {
+        self.object_layers.iter().find(|l| l.name == name)
+    }
+
+    /// Iterates over the objects of all object layers.
+    pub fn objects(&self) -> impl Iterator<Item = &MapObject> {
+        self.object_layers.iter().flat_map(|l| l.objects.iter())
+    }
+
+    /// Gets the first object with the given name of all object layers.
+    pub fn object_by_name(&self, name: &str) -> Option<&MapObject> {
+        self.objects().find(|o| o.name == name)
+    }
+
+    /// Gets the objects of the given kind of all object layers.
+    pub fn objects_by_kind<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a MapObject> {
+        self.objects().filter(move |o| o.kind == kind)
+    }
+
+    /// Gets the objects of all object layers whose shape contains the given pixel position.
+    pub fn objects_at(&self, x: f64, y: f64) -> impl Iterator<Item = &MapObject> {
+        self.objects().filter(move |o| o.shape.contains(x, y))
+    }
+
+    /// Marks the whole map as changed for the observers of `changes`.
+    pub fn mark_changed(&mut self) {
+        self.changes.record_all();
+    }
+
+    // Changes a property of the layer with the given index and marks the map as changed if the
+    // value is different.
+    fn set_layer_property<T: PartialEq>(
+        &mut self,
+        layer: usize,
+        value: T,
+        property: impl Fn(&mut Layer) -> &mut T,
+    ) {
+        if let Some(l) = self.layers.get_mut(layer) {
+            let current = property(l);
+
+            if *current != value {
+                *current = value;
+                self.changes.record_all();
+            }
+        }
+    }
+
+    /// Shows or hides the layer with the given index.
+    pub fn set_layer_visible(&mut self, layer: usize, visible: bool) {
+        self.set_layer_property(layer, visible, |l| &mut l.visible);
+    }
+
+    /// Sets the opacity of the layer with the given index.
+    pub fn set_layer_opacity(&mut self, layer: usize, opacity: f32) {
+        self.set_layer_property(layer, opacity, |l| &mut l.opacity);
+    }
+
+    /// Sets the tint of the layer with the given index.
+    pub fn set_layer_tint(&mut self, layer: usize, tint: (u8, u8, u8)) {
+        self.set_layer_property(layer, tint, |l| &mut l.tint);
+    }
+
+    /// Sets the parallax factor of the layer with the given index.
+    pub fn set_layer_parallax(&mut self, layer: usize, parallax: (f64, f64)) {
+        self.set_layer_property(layer, parallax, |l| &mut l.parallax);
+    }
+
+    /// Replaces the tile sets of the map.
+    pub fn set_tile_sets(&mut self, tile_sets: Vec<TileSet>) {
+        self.tile_sets = tile_sets;
+        self.changes.record_all();
+    }
+
+    /// Replaces the blocked tiles of the map.
+    pub fn set_blocked_tiles(&mut self, blocked_tiles: Vec<i32>) {
+        self.blocked_tiles = blocked_tiles;
+        self.changes.record_all();
+    }
+
     /// Sets the layer count.
     pub fn set_layer_count(&mut self, layer_count: usize) {
+        self.changes.record_all();
+
         // add new layers
         if self.layer_count < layer_count {
             for _ in 0..(layer_count - self.layer_count) {
@@ -81,6 +550,8 @@ impl Map {
     }
 
     pub fn set_row_count(&mut self, row_count: usize) {
+        self.changes.record_all();
+
         if self.row_count < row_count {
             let offset = row_count - self.row_count;
 
@@ -117,6 +588,8 @@ impl Map {
     }
 
     pub fn set_column_count(&mut self, column_count: usize) {
+        self.changes.record_all();
+
         if self.column_count > column_count {
             let offset = self.column_count - column_count;
             for r in (0..self.row_count).rev() {
@@ -153,13 +626,56 @@ impl Map {
         self.tile_size
     }
 
+    /// Gets the number of columns and rows the largest tiles of the tile sets reach beyond their
+    /// own tile. Like in Tiled, tiles are anchored at the bottom left of their tile, so larger
+    /// tiles reach to the right and up.
+    pub fn tile_overhang(&self) -> (usize, usize) {
+        if self.tile_size == 0 {
+            return (0, 0);
+        }
+
+        let overhang = |size: u32| {
+            (size.saturating_sub(self.tile_size) as f64 / self.tile_size as f64).ceil() as usize
+        };
+
+        self.tile_sets.iter().fold((0, 0), |(columns, rows), t| {
+            (
+                cmp::max(columns, overhang(t.tile_width)),
+                cmp::max(rows, overhang(t.tile_height)),
+            )
+        })
+    }
+
+    /// Gets the id of the tile at the given position, the flip flags of the tile are ignored.
     pub fn get_tile(&self, layer: usize, row: usize, column: usize) -> i32 {
+        self.get_tile_with_flags(layer, row, column).id
+    }
+
+    /// Gets the column and the row of the tile at the given pixel position of the world. Returns
+    /// `None` if the position is outside of the map.
+    pub fn tile_at(&self, position: Point) -> Option<(usize, usize)> {
+        if self.tile_size == 0 || position.x() < 0.0 || position.y() < 0.0 {
+            return None;
+        }
+
+        let column = (position.x() / self.tile_size as f64) as usize;
+        let row = (position.y() / self.tile_size as f64) as usize;
+
+        if column >= self.column_count || row >= self.row_count {
+            return None;
+        }
+
+        Some((column, row))
+    }
+
+    /// Gets the tile at the given position with its flip flags.
+    pub fn get_tile_with_flags(&self, layer: usize, row: usize, column: usize) -> Tile {
         if let Some(l) = self.layers.get(layer) {
             if let Some(t) = l.tiles.get(row * self.column_count + column) {
-                return *t;
+                return Tile::from_entry(*t);
             }
         }
-        -1
+        Tile::default()
     }
 
     pub fn get_column(&self, x: f32) -> f32 {
@@ -178,10 +694,163 @@ impl Map {
         row * self.tile_size as f32
     }
 
+    /// Gets the index of the tile set that contains the given tile and the id of the tile inside
+    /// of that tile set.
+    pub fn tile_set_index(&self, tile: i32) -> Option<(usize, u32)> {
+        let tile = tile_id(tile);
+
+        if tile < 0 {
+            return None;
+        }
+
+        let tile = tile as u32;
+
+        self.tile_sets
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.first_id <= tile)
+            .max_by_key(|(_, t)| t.first_id)
+            .map(|(i, t)| (i, tile - t.first_id))
+    }
+
+    /// Gets the tile set that contains the given tile. Like all tile queries of the map, the flip
+    /// flags of the tile are ignored.
+    pub fn tile_set(&self, tile: i32) -> Option<&TileSet> {
+        self.tile_set_index(tile).map(|(i, _)| &self.tile_sets[i])
+    }
+
+    /// Gets the additional tile set data of the given tile.
+    pub fn tile_data(&self, tile: i32) -> Option<&TileData> {
+        self.tile_set_index(tile)
+            .and_then(|(i, id)| self.tile_sets[i].tile(id))
+    }
+
+    /// Checks if the given tile has an animation.
+    pub fn is_animated(&self, tile: i32) -> bool {
+        match self.tile_data(tile) {
+            Some(data) => !data.animation.is_empty(),
+            None => false,
+        }
+    }
+
+    /// Gets the tile that is drawn for the given tile after the elapsed milliseconds. Tiles without
+    /// an animation are returned unchanged.
+    pub fn animated_tile(&self, tile: i32, elapsed: f64) -> i32 {
+        match self.tile_set_index(tile) {
+            Some((i, id)) => {
+                let tile_set = &self.tile_sets[i];
+                (tile_set.first_id + tile_set.animated_tile(id, elapsed)) as i32
+            }
+            None => tile,
+        }
+    }
+
+    /// Checks if an animated tile inside of the camera shows another frame at `to` than at `from`.
+    /// The tiles inside of the camera are worked out for each layer with its parallax factor.
+    pub fn animation_changed(&self, camera: &Camera, from: f64, to: f64) -> bool {
+        if self.tile_size == 0 || from == to {
+            return false;
+        }
+
+        // larger tiles of tile sets reach into the camera from the left and from below
+        let tile_size = self.tile_size as f64;
+        let (overhang_columns, overhang_rows) = self.tile_overhang();
+        let range = |position: f64, size: f64, before: usize, after: usize| {
+            ((position / tile_size).floor().max(0.0) as usize).saturating_sub(before)
+                ..((position + size) / tile_size).ceil().max(0.0) as usize + 1 + after
+        };
+
+        self.layers.iter().filter(|l| l.visible).any(|layer| {
+            self.layer_animation_changed(
+                layer,
+                range(
+                    camera.x() * layer.parallax.0,
+                    camera.view_width(),
+                    overhang_columns,
+                    0,
+                ),
+                range(
+                    camera.y() * layer.parallax.1,
+                    camera.view_height(),
+                    0,
+                    overhang_rows,
+                ),
+                from,
+                to,
+            )
+        })
+    }
+
+    /// Checks if an animated tile inside of the given columns and rows shows another frame at `to`
+    /// than at `from`.
+    pub fn animation_changed_in(
+        &self,
+        columns: Range<usize>,
+        rows: Range<usize>,
+        from: f64,
+        to: f64,
+    ) -> bool {
+        if from == to {
+            return false;
+        }
+
+        self.layers.iter().filter(|l| l.visible).any(|layer| {
+            self.layer_animation_changed(layer, columns.clone(), rows.clone(), from, to)
+        })
+    }
+
+    // Checks if an animated tile of the layer inside of the given columns and rows changes its
+    // frame.
+    fn layer_animation_changed(
+        &self,
+        layer: &Layer,
+        columns: Range<usize>,
+        rows: Range<usize>,
+        from: f64,
+        to: f64,
+    ) -> bool {
+        let columns = columns.start..columns.end.min(self.column_count);
+        let rows = rows.start..rows.end.min(self.row_count);
+
+        for row in rows {
+            for column in columns.clone() {
+                let tile = match layer.tiles.get(row * self.column_count + column) {
+                    Some(tile) => *tile,
+                    None => continue,
+                };
+
+                if self.is_animated(tile)
+                    && self.animated_tile(tile, from) != self.animated_tile(tile, to)
+                {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Gets the collision shape of the given tile. Tiles of `blocked_tiles` are fully blocked.
+    pub fn collision(&self, tile: i32) -> &Collision {
+        if self.blocked_tiles.contains(&tile_id(tile)) {
+            return &Collision::Full;
+        }
+
+        match self.tile_data(tile) {
+            Some(data) => &data.collision,
+            None => &Collision::None,
+        }
+    }
+
+    /// Checks if any part of the tile at the given column and row is blocked on any layer.
     pub fn is_blocked(&self, column: usize, row: usize) -> bool {
+        if column >= self.column_count || row >= self.row_count {
+            return false;
+        }
+
         for l in &self.layers {
             if let Some(t) = l.tiles.get(row * self.column_count + column) {
-                if self.blocked_tiles.contains(&t) {
+                if self.collision(*t).is_blocking() {
                     return true;
                 }
             }
@@ -190,17 +859,75 @@ impl Map {
         false
     }
 
+    /// Finds the cheapest path between the given (column, row) tiles with A*. The path contains
+    /// the steps after the start up to and including the goal, it is empty if start and goal are
+    /// the same. Returns `None` if the goal is outside the map, blocked or cannot be reached
+    /// within the maximum visited tiles of the options.
+    pub fn find_path(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        options: &PathOptions,
+    ) -> Option<Vec<(usize, usize)>> {
+        path::find_path(self, start, goal, options)
+    }
+
+    /// Sets the tile entry at the given position. The entry could contain flip flags.
     pub fn set_tile(&mut self, layer: usize, column: usize, row: usize, tile: i32) {
-        if let Some(layer) = self.layers.get_mut(layer) {
-            layer.set_tile(row * self.column_count + column, tile);
+        if column >= self.column_count {
+            return;
+        }
+
+        let index = row * self.column_count + column;
+
+        if let Some(t) = self
+            .layers
+            .get_mut(layer)
+            .and_then(|l| l.tiles.get_mut(index))
+        {
+            if *t != tile {
+                *t = tile;
+                self.changes.record(column, row);
+            }
         }
     }
 
+    /// Sets the tile at the given position with its flip flags.
+    pub fn set_tile_with_flags(&mut self, layer: usize, column: usize, row: usize, tile: Tile) {
+        self.set_tile(layer, column, row, tile.to_entry());
+    }
+
+    /// Checks if the given pixel position is blocked by the collision shape of a tile on any layer.
+    /// The collision shapes are flipped with their tiles.
     pub fn is_tile_blocked(&self, x: f32, y: f32) -> bool {
-        let column = (x / self.tile_size as f32).floor() as usize;
-        let row = (y / self.tile_size as f32).floor() as usize;
+        if x < 0.0 || y < 0.0 || self.tile_size == 0 {
+            return false;
+        }
 
-        self.is_blocked(column, row)
+        let tile_size = self.tile_size as f32;
+        let column = (x / tile_size).floor() as usize;
+        let row = (y / tile_size).floor() as usize;
+
+        if column >= self.column_count || row >= self.row_count {
+            return false;
+        }
+
+        let local_x = (x - column as f32 * tile_size) as f64;
+        let local_y = (y - row as f32 * tile_size) as f64;
+
+        let size = tile_size as f64;
+
+        self.layers
+            .iter()
+            .filter_map(|l| l.tiles.get(row * self.column_count + column))
+            .any(|t| {
+                let tile = Tile::from_entry(*t);
+                let x = if tile.flip_x { size - local_x } else { local_x };
+                let y = if tile.flip_y { size - local_y } else { local_y };
+                let (x, y) = if tile.diagonal { (y, x) } else { (x, y) };
+
+                self.collision(tile.id).contains(x, y, size, size)
+            })
     }
 
     pub fn to_string(&self) -> String {
@@ -208,31 +935,435 @@ impl Map {
     }
 }
 
-// todo return with result!
-impl From<&str> for Map {
-    fn from(s: &str) -> Self {
-        if let Ok(file) = &mut File::open(s) {
-            let mut contents = String::new();
-            file.read_to_string(&mut contents).unwrap();
+// Entries below -1 are valid if they are flipped tiles. The bit below the flip flags is not
+// used, so small negative numbers like -2 are still invalid.
+fn is_valid_entry(entry: i32) -> bool {
+    entry >= -1 || entry as u32 & 0x1000_0000 == 0
+}
 
-            let map: Map = match from_str(contents.as_str()) {
-                Ok(x) => x,
-                Err(e) => {
-                    println!("Failed to load config: {:?}", e);
+// Checks if the layer contains the tile with or without flip flags.
+fn contains_tile(layer: &Layer, tile: i32) -> bool {
+    layer.tiles.iter().any(|t| tile_id(*t) == tile)
+}
 
-                    std::process::exit(1);
-                }
-            };
+impl TryFrom<&str> for Map {
+    type Error = MapError;
 
-            map
-        } else {
-            panic!("Could not load file {}", s);
-        }
+    fn try_from(path: &str) -> Result<Self, Self::Error> {
+        Map::load(path)
     }
 }
 
-impl From<String> for Map {
-    fn from(string: String) -> Self {
-        Map::from(string.as_str())
+impl TryFrom<String> for Map {
+    type Error = MapError;
+
+    fn try_from(path: String) -> Result<Self, Self::Error> {
+        Map::load(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use crate::prelude::*;
+
+    const MAP: &str = "(
+        layer_count: 1,
+        row_count: 2,
+        column_count: 2,
+        tile_size: 32,
+        blocked_tiles: [3],
+        layers: [(tiles: [0, 1, 2, 3])],
+    )";
+
+    #[test]
+    fn test_from_ron_str() {
+        let map = Map::from_ron_str(MAP).unwrap();
+        assert_eq!(2, map.row_count());
+        assert_eq!(3, map.get_tile(0, 1, 1));
+    }
+
+    #[test]
+    fn test_from_ron_str_parse_error() {
+        match Map::from_ron_str("(\n    layer_count: x,\n)") {
+            Err(MapError::Parse { line, .. }) => assert_eq!(2, line),
+            e => panic!("unexpected result {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_from_ron_str_structure_error() {
+        let result = Map::from_ron_str(
+            "(layer_count: 2, row_count: 1, column_count: 1, tile_size: 8, blocked_tiles: [], layers: [(tiles: [0])])",
+        );
+        assert!(matches!(result, Err(MapError::Structure(_))));
+    }
+
+    #[test]
+    fn test_from_ron_str_unused_blocked_tile() {
+        // a blocked tile that is not placed yet is only a warning
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 1, tile_size: 8, blocked_tiles: [7], layers: [(tiles: [0])])",
+        )
+        .unwrap();
+        assert_eq!(vec![7], map.blocked_tiles);
+        assert_eq!(Ok(vec![ValidationError::UnusedBlockedTile(7)]), map.check());
+
+        map.validate().iter().for_each(|e| assert!(e.is_warning()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut map = Map::from_ron_str(MAP).unwrap();
+        assert!(map.validate().is_empty());
+
+        map.layers[0].tiles.pop();
+        map.layers[0].tiles[0] = -3;
+        map.blocked_tiles.push(9);
+        map.tile_size = 0;
+        map.layer_count = 2;
+
+        assert_eq!(
+            vec![
+                ValidationError::LayerCount {
+                    expected: 2,
+                    found: 1
+                },
+                ValidationError::ZeroTileSize,
+                ValidationError::TileCount {
+                    layer: 0,
+                    expected: 4,
+                    found: 3
+                },
+                ValidationError::InvalidTile {
+                    layer: 0,
+                    index: 0,
+                    tile: -3
+                },
+                ValidationError::UnusedBlockedTile(3),
+                ValidationError::UnusedBlockedTile(9),
+            ],
+            map.validate()
+        );
+    }
+
+    #[test]
+    fn test_from_ron_str_repair() {
+        let s = "(layer_count: 2, row_count: 1, column_count: 2, tile_size: 8, blocked_tiles: [5], layers: [(tiles: [-4])])";
+
+        assert!(Map::from_ron_str_with(s, Validation::Skip).is_ok());
+
+        let map = Map::from_ron_str_with(s, Validation::Repair).unwrap();
+        assert_eq!(vec![-1, -1], map.layers[0].tiles);
+        assert_eq!(vec![-1, -1], map.layers[1].tiles);
+        assert_eq!(vec![5], map.blocked_tiles);
+    }
+
+    #[test]
+    fn test_layer_defaults() {
+        let map = Map::from_ron_str(MAP).unwrap();
+        let layer = &map.layers[0];
+
+        assert!(layer.visible);
+        assert_eq!(1.0, layer.opacity);
+        assert!(layer.is_untinted());
+        assert!(!layer.has_parallax());
+        assert!(layer.name.is_empty());
+        assert!(map.image_layers.is_empty());
+    }
+
+    #[test]
+    fn test_image_layers() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 1, tile_size: 8, blocked_tiles: [],
+                layers: [(parallax: (0.5, 1.0), tiles: [0])],
+                image_layers: [(name: \"sky\", image: \"sky.png\", parallax: (0.25, 0.0), repeat_x: true)],
+            )",
+        )
+        .unwrap();
+
+        assert_eq!((0.5, 1.0), map.layers[0].parallax);
+        assert!(map.layers[0].has_parallax());
+
+        let sky = &map.image_layers[0];
+        assert_eq!("sky.png", sky.image);
+        assert_eq!((0.25, 0.0), sky.parallax);
+        assert!(sky.repeat_x && !sky.repeat_y);
+        assert_eq!(1.0, sky.opacity);
+
+        let map = Map::from_ron_str(&map.to_ron_string().unwrap()).unwrap();
+        assert_eq!(sky, &map.image_layers[0]);
+    }
+
+    #[test]
+    fn test_layer_by_name() {
+        let map = Map::from_ron_str(
+            "(layer_count: 2, row_count: 1, column_count: 1, tile_size: 8, blocked_tiles: [], layers: [
+                (name: \"floor\", tiles: [0]),
+                (name: \"roof\", visible: false, opacity: 0.5, tint: (255, 0, 0), properties: {\"kind\": \"glass\"}, tiles: [1]),
+            ])",
+        )
+        .unwrap();
+
+        let roof = map.layer_by_name("roof").unwrap();
+        assert!(!roof.visible);
+        assert_eq!(0.5, roof.opacity);
+        assert_eq!((255, 0, 0), roof.tint);
+        assert_eq!(Some("glass"), roof.property("kind"));
+        assert_eq!(vec![0], map.layer_by_name("floor").unwrap().tiles);
+        assert!(map.layer_by_name("sky").is_none());
+
+        assert_eq!(
+            map,
+            Map::from_ron_str(map.to_ron_string().unwrap().as_str()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_object_layers() {
+        let map = Map::from_ron_str(
+            "(layer_count: 0, row_count: 0, column_count: 0, tile_size: 8, blocked_tiles: [], layers: [],
+                object_layers: [
+                    (name: \"entities\", objects: [
+                        (id: 1, name: \"player\", kind: \"spawn\", shape: Point(x: 8.0, y: 8.0)),
+                        (id: 2, name: \"orc\", kind: \"spawn\", shape: Point(x: 32.0, y: 8.0)),
+                        (id: 3, name: \"patrol\", kind: \"path\", shape: Polyline([(0.0, 0.0), (16.0, 0.0)])),
+                    ]),
+                    (name: \"triggers\", objects: [
+                        (id: 4, name: \"exit\", kind: \"door\", shape: Rect(x: 0.0, y: 0.0, width: 16.0, height: 16.0),
+                            properties: {\"target\": \"level_2\"}),
+                    ]),
+                ],
+            )",
+        )
+        .unwrap();
+
+        assert_eq!(4, map.objects().count());
+        assert_eq!(2, map.objects_by_kind("spawn").count());
+        assert_eq!(
+            Some("level_2"),
+            map.object_by_name("exit").unwrap().property("target")
+        );
+        assert_eq!(
+            vec![1, 4],
+            map.objects_at(8.0, 8.0).map(|o| o.id).collect::<Vec<u32>>()
+        );
+        assert!(map.object_layer_by_name("triggers").is_some());
+
+        assert_eq!(
+            map,
+            Map::from_ron_str(map.to_ron_string().unwrap().as_str()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tile_set_collision() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 3, tile_size: 16, blocked_tiles: [2], layers: [(tiles: [0, 1, 2])],
+                tile_sets: [(name: \"dungeon\", image: \"tile_set.png\", tile_width: 16, tile_height: 16, tiles: [
+                    (id: 1, collision: Half(Bottom), terrain: Some(\"wall\"), properties: {\"sound\": \"stone\"}),
+                ])],
+            )",
+        )
+        .unwrap();
+
+        assert!(!map.is_blocked(0, 0));
+        assert!(map.is_blocked(1, 0));
+        assert!(map.is_blocked(2, 0));
+        assert!(!map.is_blocked(3, 0));
+
+        assert!(!map.is_tile_blocked(20.0, 4.0));
+        assert!(map.is_tile_blocked(20.0, 12.0));
+        assert!(map.is_tile_blocked(36.0, 4.0));
+        assert!(!map.is_tile_blocked(-4.0, 4.0));
+
+        let data = map.tile_data(1).unwrap();
+        assert_eq!(Some("wall"), data.terrain.as_deref());
+        assert_eq!(Some("stone"), data.property("sound"));
+    }
+
+    #[test]
+    fn test_tile_at() {
+        let map = Map {
+            row_count: 2,
+            column_count: 3,
+            tile_size: 16,
+            ..Map::default()
+        };
+
+        assert_eq!(Some((0, 0)), map.tile_at(Point::new(0.0, 0.0)));
+        assert_eq!(Some((2, 1)), map.tile_at(Point::new(47.9, 31.0)));
+        assert_eq!(None, map.tile_at(Point::new(48.0, 0.0)));
+        assert_eq!(None, map.tile_at(Point::new(-0.5, 0.0)));
+        assert_eq!(None, map.tile_at(Point::new(0.0, 32.0)));
+
+        // with a camera
+        let camera = CameraBuilder::new().x(16.0).zoom(2.0).build();
+        assert_eq!(
+            Some((2, 1)),
+            map.tile_at(camera.screen_to_world(Point::new(40.0, 40.0)))
+        );
+    }
+
+    #[test]
+    fn test_tile_flags() {
+        let mut map = Map::from_ron_str(MAP).unwrap();
+        map.tile_sets.push(TileSet::new("tile_set.png", 32, 32));
+        map.tile_sets[0].tiles.push(TileData {
+            id: 1,
+            collision: Collision::Half(Side::Bottom),
+            ..TileData::default()
+        });
+
+        assert!(map.is_tile_blocked(40.0, 24.0));
+        map.set_tile_with_flags(0, 1, 0, Tile::new(1).flipped_y());
+        map.set_tile_with_flags(0, 1, 1, Tile::new(3).rotated());
+
+        assert_eq!(1, map.get_tile(0, 0, 1));
+        assert_eq!(Tile::new(1).flipped_y(), map.get_tile_with_flags(0, 0, 1));
+        assert!(map.get_tile_with_flags(0, 1, 1).diagonal);
+        assert!(map.validate().is_empty());
+        assert!(map.is_blocked(1, 1));
+
+        // the bottom half collision is flipped to the top
+        assert!(!map.is_tile_blocked(40.0, 24.0));
+        assert!(map.is_tile_blocked(40.0, 8.0));
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut map = Map::from_ron_str(MAP).unwrap();
+        let stamp = map.changes.stamp();
+
+        map.set_tile(0, 1, 1, 7);
+        map.set_tile(0, 0, 0, 0);
+        map.set_tile(0, 2, 0, 7);
+        assert_eq!(
+            vec![(1, 1)],
+            map.changes.since(stamp).unwrap().collect::<Vec<_>>()
+        );
+
+        let clone = map.clone();
+        assert_eq!(1, clone.changes.since(stamp).unwrap().count());
+
+        // changes of the layers and the tile sets mark the whole map as changed
+        let stamp = map.changes.stamp();
+        map.set_layer_visible(0, true);
+        map.set_layer_opacity(0, 1.0);
+        map.layer_by_name_mut("").unwrap().tiles[0] = 1;
+        assert_eq!(Some(0), map.changes.since(stamp).map(Iterator::count));
+
+        map.set_layer_visible(map.layer_index("").unwrap(), false);
+        assert!(map.changes.since(stamp).is_none());
+
+        for change in &[
+            |m: &mut Map| m.set_layer_opacity(0, 0.5),
+            |m: &mut Map| m.set_layer_tint(0, (255, 0, 0)),
+            |m: &mut Map| m.set_layer_parallax(0, (0.5, 1.0)),
+            |m: &mut Map| m.set_tile_sets(vec![]),
+            |m: &mut Map| m.set_blocked_tiles(vec![]),
+        ] {
+            let stamp = map.changes.stamp();
+            change(&mut map);
+            assert!(map.changes.since(stamp).is_none());
+        }
+
+        assert!(!map.layers[0].visible);
+        assert_eq!(0.5, map.layers[0].opacity);
+        assert_eq!((255, 0, 0), map.layers[0].tint);
+        assert_eq!((0.5, 1.0), map.layers[0].parallax);
+    }
+
+    #[test]
+    fn test_tile_set_index() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 3, tile_size: 16, blocked_tiles: [], layers: [(tiles: [0, 64, 70])],
+                tile_sets: [
+                    (name: \"props\", first_id: 64, tiles: [(id: 6, collision: Full)]),
+                    (name: \"terrain\", first_id: 0),
+                ],
+            )",
+        )
+        .unwrap();
+
+        assert_eq!(None, map.tile_set_index(-1));
+        assert_eq!(Some((1, 5)), map.tile_set_index(5));
+        assert_eq!(Some((0, 0)), map.tile_set_index(64));
+        assert_eq!("props", map.tile_set(70).unwrap().name);
+        assert!(!map.is_blocked(1, 0));
+        assert!(map.is_blocked(2, 0));
+    }
+
+    #[test]
+    fn test_tile_overhang() {
+        let mut map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 1, tile_size: 16, blocked_tiles: [], layers: [(tiles: [0])],
+                tile_sets: [(tile_width: 16, tile_height: 16), (first_id: 8, tile_width: 8, tile_height: 8)],
+            )",
+        )
+        .unwrap();
+        assert_eq!((0, 0), map.tile_overhang());
+
+        map.tile_sets[1].tile_width = 32;
+        map.tile_sets[1].tile_height = 40;
+        assert_eq!((1, 2), map.tile_overhang());
+    }
+
+    #[test]
+    fn test_animated_tile() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 1, column_count: 2, tile_size: 16, blocked_tiles: [], layers: [(tiles: [10, 11])],
+                tile_sets: [
+                    (first_id: 10, tiles: [(id: 0, animation: [(tile: 0, duration: 200), (tile: 2, duration: 100)])]),
+                ],
+            )",
+        )
+        .unwrap();
+
+        assert!(map.is_animated(10));
+        assert!(!map.is_animated(11));
+        assert_eq!(10, map.animated_tile(10, 199.0));
+        assert_eq!(12, map.animated_tile(10, 200.0));
+        assert_eq!(10, map.animated_tile(10, 300.0));
+        assert_eq!(11, map.animated_tile(11, 200.0));
+        assert_eq!(-1, map.animated_tile(-1, 200.0));
+
+        let camera = CameraBuilder::new().width(16.0).height(16.0).build();
+        assert!(map.animation_changed(&camera, 100.0, 250.0));
+        assert!(!map.animation_changed(&camera, 100.0, 150.0));
+
+        let camera = CameraBuilder::new()
+            .x(32.0)
+            .width(16.0)
+            .height(16.0)
+            .build();
+        assert!(!map.animation_changed(&camera, 100.0, 250.0));
+
+        // the layer scrolls slower and the animated tile is still visible
+        let mut map = map;
+        map.layers[0].parallax = (0.25, 1.0);
+        assert!(map.animation_changed(&camera, 100.0, 250.0));
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        assert!(matches!(
+            Map::load("does/not/exist.ron"),
+            Err(MapError::Io { .. })
+        ));
+        assert!(matches!(
+            Map::try_from("does/not/exist.ron"),
+            Err(MapError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let map = Map::from_ron_str(MAP).unwrap();
+        let path = std::env::temp_dir().join("orbgame_test_save_and_load.ron");
+        map.save(&path).unwrap();
+        assert_eq!(map, Map::load(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
     }
 }
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Describes the shape of a map object. All coordinates are in pixels relative to the map origin.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Shape {
+    Point {
+        x: f64,
+        y: f64,
+    },
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    Ellipse {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    /// An open path, e.g. a patrol path.
+    Polyline(Vec<(f64, f64)>),
+    /// A closed region.
+    Polygon(Vec<(f64, f64)>),
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape::Point { x: 0.0, y: 0.0 }
+    }
+}
+
+impl Shape {
+    /// Checks if the given point is inside of the shape. Points and polylines do not have an area
+    /// and contain only their exact points.
+    pub fn contains(&self, px: f64, py: f64) -> bool {
+        match self {
+            Shape::Point { x, y } => *x == px && *y == py,
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => px >= *x && px <= x + width && py >= *y && py <= y + height,
+            Shape::Ellipse {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                if *width <= 0.0 || *height <= 0.0 {
+                    return false;
+                }
+
+                let rx = width / 2.0;
+                let ry = height / 2.0;
+                let dx = (px - x - rx) / rx;
+                let dy = (py - y - ry) / ry;
+
+                dx * dx + dy * dy <= 1.0
+            }
+            Shape::Polyline(points) => points.iter().any(|p| p.0 == px && p.1 == py),
+            Shape::Polygon(points) => polygon_contains(points, px, py),
+        }
+    }
+}
+
+// Checks if the point is inside of the polygon with the even-odd rule.
+pub(crate) fn polygon_contains(points: &[(f64, f64)], px: f64, py: f64) -> bool {
+    let mut inside = false;
+    let mut j = points.len().wrapping_sub(1);
+
+    for i in 0..points.len() {
+        let (xi, yi) = points[i];
+        let (xj, yj) = points[j];
+
+        if (yi > py) != (yj > py) && px < (xj - xi) * (py - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+
+        j = i;
+    }
+
+    inside
+}
+
+/// Describes an object that is placed on a map like a spawn point, a door, a trigger or a path.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapObject {
+    /// The unique id of the object.
+    pub id: u32,
+
+    /// The name of the object.
+    pub name: String,
+
+    /// The kind of the object, e.g. `spawn` or `door`.
+    pub kind: String,
+
+    /// The shape of the object.
+    pub shape: Shape,
+
+    /// Free-form properties of the object.
+    pub properties: BTreeMap<String, String>,
+}
+
+impl MapObject {
+    /// Gets the property with the given key.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+}
+
+/// Describes a layer of objects of a map.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObjectLayer {
+    /// The name of the layer.
+    pub name: String,
+
+    /// The visibility of the layer e.g. for editors.
+    pub visible: bool,
+
+    /// Free-form properties of the layer.
+    pub properties: BTreeMap<String, String>,
+
+    pub objects: Vec<MapObject>,
+}
+
+impl Default for ObjectLayer {
+    fn default() -> Self {
+        ObjectLayer {
+            name: String::default(),
+            visible: true,
+            properties: BTreeMap::new(),
+            objects: vec![],
+        }
+    }
+}
+
+impl ObjectLayer {
+    /// Creates a new object layer with the given name and objects.
+    pub fn new(name: impl Into<String>, objects: Vec<MapObject>) -> Self {
+        ObjectLayer {
+            name: name.into(),
+            objects,
+            ..ObjectLayer::default()
+        }
+    }
+
+    /// Gets the first object with the given name.
+    pub fn object_by_name(&self, name: &str) -> Option<&MapObject> {
+        self.objects.iter().find(|o| o.name == name)
+    }
+
+    /// Gets all objects of the given kind.
+    pub fn objects_by_kind<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a MapObject> {
+        self.objects.iter().filter(move |o| o.kind == kind)
+    }
+
+    /// Gets all objects whose shape contains the given point.
+    pub fn objects_at(&self, x: f64, y: f64) -> impl Iterator<Item = &MapObject> {
+        self.objects.iter().filter(move |o| o.shape.contains(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_contains() {
+        let rect = Shape::Rect {
+            x: 10.0,
+            y: 10.0,
+            width: 20.0,
+            height: 10.0,
+        };
+        assert!(rect.contains(10.0, 20.0));
+        assert!(!rect.contains(31.0, 15.0));
+
+        let ellipse = Shape::Ellipse {
+            x: 0.0,
+            y: 0.0,
+            width: 20.0,
+            height: 10.0,
+        };
+        assert!(ellipse.contains(10.0, 5.0));
+        assert!(ellipse.contains(19.0, 5.0));
+        assert!(!ellipse.contains(1.0, 1.0));
+
+        let polygon = Shape::Polygon(vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]);
+        assert!(polygon.contains(2.0, 2.0));
+        assert!(!polygon.contains(8.0, 8.0));
+
+        assert!(Shape::Point { x: 1.0, y: 2.0 }.contains(1.0, 2.0));
+        assert!(!Shape::Polyline(vec![(0.0, 0.0), (10.0, 0.0)]).contains(5.0, 0.0));
+    }
+}
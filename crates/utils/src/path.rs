@@ -0,0 +1,445 @@
+use std::{cmp::Ordering, collections::BinaryHeap, f64::consts::SQRT_2, fmt};
+
+use crate::Map;
+
+/// Describes the directions a path can move between tiles.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Movement {
+    /// Moves only horizontally and vertically.
+    FourWay,
+
+    /// Moves also diagonally. A diagonal step costs the square root of 2 times the tile cost.
+    EightWay,
+}
+
+/// Describes if a diagonal step may pass the corner of a blocked tile.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CornerCutting {
+    /// Diagonal steps are allowed even between two blocked tiles.
+    Always,
+
+    /// Diagonal steps are allowed if at least one of the two tiles next to the step is free.
+    IfOneFree,
+
+    /// Diagonal steps are only allowed if both tiles next to the step are free.
+    Never,
+}
+
+/// Gets the cost to enter the tile at the given column and row, `None` if the tile cannot be
+/// entered.
+pub type TileCostFn<'a> = dyn Fn(usize, usize) -> Option<f64> + 'a;
+
+/// Describes how `Map::find_path` searches a path.
+///
+/// By default a path moves in four directions and each tile that is not blocked costs 1.
+pub struct PathOptions<'a> {
+    movement: Movement,
+    corner_cutting: CornerCutting,
+    cost: Option<Box<TileCostFn<'a>>>,
+    max_visited: usize,
+}
+
+impl<'a> Default for PathOptions<'a> {
+    fn default() -> Self {
+        PathOptions {
+            movement: Movement::FourWay,
+            corner_cutting: CornerCutting::Never,
+            cost: None,
+            max_visited: usize::MAX,
+        }
+    }
+}
+
+impl<'a> fmt::Debug for PathOptions<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PathOptions")
+            .field("movement", &self.movement)
+            .field("corner_cutting", &self.corner_cutting)
+            .field("cost", &self.cost.is_some())
+            .field("max_visited", &self.max_visited)
+            .finish()
+    }
+}
+
+impl<'a> PathOptions<'a> {
+    /// Creates new path options with default values.
+    pub fn new() -> Self {
+        PathOptions::default()
+    }
+
+    /// Inserts the movement.
+    pub fn movement(mut self, movement: Movement) -> Self {
+        self.movement = movement;
+        self
+    }
+
+    /// Inserts the corner cutting rule of diagonal steps.
+    pub fn corner_cutting(mut self, corner_cutting: CornerCutting) -> Self {
+        self.corner_cutting = corner_cutting;
+        self
+    }
+
+    /// Inserts the cost function that gets the cost to enter the tile at the given column and
+    /// row, e.g. higher costs for swamps. Returning `None` blocks the tile in addition to the
+    /// blocked tiles of the map.
+    ///
+    /// Costs should be at least 1, otherwise the found path is not always the shortest. Negative
+    /// and infinite costs block the tile.
+    pub fn cost<F: Fn(usize, usize) -> Option<f64> + 'a>(mut self, cost: F) -> Self {
+        self.cost = Some(Box::new(cost));
+        self
+    }
+
+    /// Inserts the maximum number of visited tiles. The search gives up if it visits more tiles,
+    /// which keeps searches for unreachable goals on large maps within a frame.
+    pub fn max_visited(mut self, max_visited: usize) -> Self {
+        self.max_visited = max_visited;
+        self
+    }
+
+    // Gets the cost to enter the given tile, `None` if it is blocked.
+    fn tile_cost(&self, map: &Map, column: usize, row: usize) -> Option<f64> {
+        if map.is_blocked(column, row) {
+            return None;
+        }
+
+        match &self.cost {
+            Some(cost) => cost(column, row).filter(|c| c.is_finite() && *c >= 0.0),
+            None => Some(1.0),
+        }
+    }
+}
+
+// An open tile of the search, ordered by the lowest estimated total cost first.
+#[derive(Copy, Clone, Debug)]
+struct Node {
+    estimate: f64,
+    cost: f64,
+    index: usize,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // prefers the node nearer to the goal on equal estimates, which visits less tiles
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                self.cost
+                    .partial_cmp(&other.cost)
+                    .unwrap_or(Ordering::Equal)
+            })
+    }
+}
+
+// Estimates the cost between two tiles with a cost of 1 for each tile.
+fn heuristic(movement: Movement, from: (usize, usize), to: (usize, usize)) -> f64 {
+    let dx = (from.0 as f64 - to.0 as f64).abs();
+    let dy = (from.1 as f64 - to.1 as f64).abs();
+
+    match movement {
+        Movement::FourWay => dx + dy,
+        Movement::EightWay => dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy),
+    }
+}
+
+const NEIGHBORS: [(isize, isize); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+];
+
+// Searches the cheapest path with A*. See `Map::find_path`.
+pub(crate) fn find_path(
+    map: &Map,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> Option<Vec<(usize, usize)>> {
+    let columns = map.column_count();
+    let rows = map.row_count();
+    let in_bounds = |(column, row): (usize, usize)| column < columns && row < rows;
+
+    if !in_bounds(start) || !in_bounds(goal) || options.tile_cost(map, goal.0, goal.1).is_none() {
+        return None;
+    }
+
+    if start == goal {
+        return Some(vec![]);
+    }
+
+    // The vectors start with zeros, which are allocated lazily, so a short search on a large map
+    // touches only the memory of the tiles it visits. The entry costs of the tiles are cached
+    // with an offset of 1, the parents are stored with an offset of 1 and 0 marks tiles that
+    // were not reached yet.
+    let mut tile_costs = vec![0.0; columns * rows];
+    let mut tile_cost = |column: usize, row: usize| {
+        let cost = &mut tile_costs[row * columns + column];
+
+        if *cost == 0.0 {
+            *cost = options
+                .tile_cost(map, column, row)
+                .map_or(f64::INFINITY, |c| c + 1.0);
+        }
+
+        *cost - 1.0
+    };
+
+    let mut costs = vec![0.0; columns * rows];
+    let mut parents = vec![0; columns * rows];
+    let mut closed = vec![false; columns * rows];
+    let mut open = BinaryHeap::new();
+    let directions = match options.movement {
+        Movement::FourWay => 4,
+        Movement::EightWay => 8,
+    };
+    let mut visited = 0;
+
+    let start_index = start.1 * columns + start.0;
+    let goal_index = goal.1 * columns + goal.0;
+    parents[start_index] = start_index + 1;
+    open.push(Node {
+        estimate: heuristic(options.movement, start, goal),
+        cost: 0.0,
+        index: start_index,
+    });
+
+    while let Some(node) = open.pop() {
+        if node.index == goal_index {
+            let mut path = vec![];
+            let mut index = goal_index;
+
+            while index != start_index {
+                path.push((index % columns, index / columns));
+                index = parents[index] - 1;
+            }
+
+            path.reverse();
+            return Some(path);
+        }
+
+        if closed[node.index] {
+            continue;
+        }
+
+        closed[node.index] = true;
+        visited += 1;
+
+        if visited > options.max_visited {
+            return None;
+        }
+
+        let column = node.index % columns;
+        let row = node.index / columns;
+
+        for &(dx, dy) in &NEIGHBORS[..directions] {
+            let next_column = column as isize + dx;
+            let next_row = row as isize + dy;
+
+            if next_column < 0
+                || next_row < 0
+                || next_column as usize >= columns
+                || next_row as usize >= rows
+            {
+                continue;
+            }
+
+            let (next_column, next_row) = (next_column as usize, next_row as usize);
+            let next = next_row * columns + next_column;
+
+            if closed[next] {
+                continue;
+            }
+
+            let mut step = tile_cost(next_column, next_row);
+
+            if step.is_infinite() {
+                continue;
+            }
+
+            if dx != 0 && dy != 0 {
+                let free = [
+                    tile_cost(next_column, row).is_finite(),
+                    tile_cost(column, next_row).is_finite(),
+                ];
+                let allowed = match options.corner_cutting {
+                    CornerCutting::Always => true,
+                    CornerCutting::IfOneFree => free[0] || free[1],
+                    CornerCutting::Never => free[0] && free[1],
+                };
+
+                if !allowed {
+                    continue;
+                }
+
+                step *= SQRT_2;
+            }
+
+            let cost = node.cost + step;
+
+            if parents[next] == 0 || cost < costs[next] {
+                costs[next] = cost;
+                parents[next] = node.index + 1;
+                open.push(Node {
+                    estimate: cost + heuristic(options.movement, (next_column, next_row), goal),
+                    cost,
+                    index: next,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    // 0 is floor, 1 is a wall
+    fn map() -> Map {
+        Map::from_ron_str(
+            "(layer_count: 1, row_count: 4, column_count: 5, tile_size: 8, blocked_tiles: [1],
+                layers: [(tiles: [
+                    0, 0, 0, 0, 0,
+                    0, 1, 1, 1, 0,
+                    0, 0, 0, 1, 0,
+                    1, 1, 0, 0, 0,
+                ])],
+            )",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_find_path() {
+        let map = map();
+        let options = PathOptions::new();
+
+        assert_eq!(Some(vec![]), map.find_path((0, 0), (0, 0), &options));
+        assert_eq!(
+            Some(vec![(0, 1), (0, 2), (1, 2), (2, 2), (2, 3)]),
+            map.find_path((0, 0), (2, 3), &options)
+        );
+
+        // blocked and outside goals
+        assert_eq!(None, map.find_path((0, 0), (1, 1), &options));
+        assert_eq!(None, map.find_path((0, 0), (5, 0), &options));
+    }
+
+    #[test]
+    fn test_find_path_unreachable() {
+        let mut map = map();
+        map.set_tile(0, 4, 2, 1);
+        map.set_tile(0, 3, 3, 1);
+
+        // the goal is enclosed by walls
+        assert_eq!(None, map.find_path((0, 0), (4, 3), &PathOptions::new()));
+        assert_eq!(
+            None,
+            map.find_path(
+                (0, 0),
+                (4, 3),
+                &PathOptions::new()
+                    .movement(Movement::EightWay)
+                    .corner_cutting(CornerCutting::Never)
+            )
+        );
+
+        // the search gives up
+        assert_eq!(
+            None,
+            map.find_path((0, 0), (2, 3), &PathOptions::new().max_visited(3))
+        );
+    }
+
+    #[test]
+    fn test_find_path_diagonal() {
+        let map = map();
+        let options = |corner_cutting| {
+            PathOptions::new()
+                .movement(Movement::EightWay)
+                .corner_cutting(corner_cutting)
+        };
+
+        // (0, 0) to (1, 2) must pass the corner of the wall at (1, 1)
+        assert_eq!(
+            Some(vec![(0, 1), (1, 2)]),
+            map.find_path((0, 0), (1, 2), &options(CornerCutting::IfOneFree))
+        );
+        assert_eq!(
+            Some(vec![(0, 1), (0, 2), (1, 2)]),
+            map.find_path((0, 0), (1, 2), &options(CornerCutting::Never))
+        );
+
+        // (2, 2) to (3, 3) passes between the walls at (3, 2) and (2, 3) only with `Always`
+        let mut map = map;
+        map.set_tile(0, 2, 3, 1);
+        let last_steps = |corner_cutting| {
+            let path = map
+                .find_path((2, 2), (3, 3), &options(corner_cutting))
+                .unwrap();
+            path[path.len().saturating_sub(2)..].to_vec()
+        };
+
+        assert_eq!(vec![(3, 3)], last_steps(CornerCutting::Always));
+        assert_eq!(vec![(4, 2), (3, 3)], last_steps(CornerCutting::IfOneFree));
+        assert_eq!(vec![(4, 3), (3, 3)], last_steps(CornerCutting::Never));
+    }
+
+    #[test]
+    fn test_find_path_cost() {
+        let map = Map::from_ron_str(
+            "(layer_count: 1, row_count: 3, column_count: 3, tile_size: 8, blocked_tiles: [],
+                layers: [(tiles: [
+                    0, 0, 0,
+                    0, 2, 0,
+                    0, 0, 0,
+                ])],
+            )",
+        )
+        .unwrap();
+
+        // the swamp in the middle is avoided
+        let swamp = |column, row| match map.get_tile(0, row, column) {
+            2 => Some(5.0),
+            _ => Some(1.0),
+        };
+        let path = map
+            .find_path((1, 0), (1, 2), &PathOptions::new().cost(swamp))
+            .unwrap();
+        assert_eq!(4, path.len());
+        assert!(!path.contains(&(1, 1)));
+
+        // and blocked by the cost function
+        let path = map.find_path(
+            (1, 0),
+            (1, 2),
+            &PathOptions::new().cost(|column, row| match (column, row) {
+                (1, 1) => None,
+                _ => Some(1.0),
+            }),
+        );
+        assert_eq!(Some(4), path.map(|p| p.len()));
+    }
+}
@@ -0,0 +1,174 @@
+use orbtk::prelude::*;
+
+// The number of noise samples per second.
+const FREQUENCY: f64 = 20.0;
+
+// Hashes the seed and the lattice point to a value between -1 and 1.
+fn lattice(seed: u64, index: i64) -> f64 {
+    // splitmix64
+    let mut z = seed
+        .wrapping_add((index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+
+    (z >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+}
+
+// Gets smooth value noise between -1 and 1 of the given seed at the given position.
+fn noise(seed: u64, position: f64) -> f64 {
+    let index = position.floor();
+    let t = position - index;
+    let t = t * t * (3.0 - 2.0 * t);
+    let a = lattice(seed, index as i64);
+    let b = lattice(seed, index as i64 + 1);
+
+    a + (b - a) * t
+}
+
+/// Describes a trauma based screen shake.
+///
+/// Hits and explosions add trauma between 0 and 1 that decays over time. The offset of the shake
+/// grows with the square of the trauma and follows smooth noise, so the same seed always shakes
+/// the same way.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shake {
+    trauma: f64,
+    time: f64,
+    max_offset: f64,
+    decay: f64,
+    seed: u64,
+}
+
+impl Default for Shake {
+    fn default() -> Self {
+        Shake {
+            trauma: 0.0,
+            time: 0.0,
+            max_offset: 16.0,
+            decay: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+impl Shake {
+    /// Creates a new shake with the given seed of the noise.
+    pub fn new(seed: u64) -> Self {
+        Shake {
+            seed,
+            ..Shake::default()
+        }
+    }
+
+    /// Gets trauma.
+    pub fn trauma(&self) -> f64 {
+        self.trauma
+    }
+
+    /// Adds trauma, the sum is clamped between 0 and 1.
+    pub fn add_trauma(&mut self, trauma: f64) {
+        self.trauma = (self.trauma + trauma).clamp(0.0, 1.0);
+    }
+
+    /// Gets max offset.
+    pub fn max_offset(&self) -> f64 {
+        self.max_offset
+    }
+
+    /// Sets the offset in pixels of the shake at a trauma of 1.
+    pub fn set_max_offset(&mut self, max_offset: f64) {
+        self.max_offset = max_offset;
+    }
+
+    /// Gets decay.
+    pub fn decay(&self) -> f64 {
+        self.decay
+    }
+
+    /// Sets the trauma that decays each second.
+    pub fn set_decay(&mut self, decay: f64) {
+        self.decay = decay;
+    }
+
+    /// Gets seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets seed.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Advances the shake by delta seconds and lets the trauma decay.
+    pub fn update(&mut self, delta: f64) {
+        if self.trauma <= 0.0 {
+            return;
+        }
+
+        self.time += delta;
+        self.trauma = (self.trauma - self.decay * delta).max(0.0);
+    }
+
+    /// Stops the shake.
+    pub fn reset(&mut self) {
+        self.trauma = 0.0;
+        self.time = 0.0;
+    }
+
+    /// Gets the current offset of the shake in pixels.
+    pub fn offset(&self) -> Point {
+        if self.trauma <= 0.0 {
+            return Point::default();
+        }
+
+        let amount = self.max_offset * self.trauma * self.trauma;
+        let position = self.time * FREQUENCY;
+
+        Point::new(
+            amount * noise(self.seed, position),
+            amount * noise(self.seed.wrapping_add(1), position),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_offset() {
+        let mut shake = Shake::new(7);
+        let mut same = Shake::new(7);
+        let mut other = Shake::new(8);
+        assert_eq!(Point::default(), shake.offset());
+
+        shake.add_trauma(0.5);
+        shake.add_trauma(0.8);
+        assert_eq!(1.0, shake.trauma());
+
+        same.add_trauma(1.0);
+        other.add_trauma(1.0);
+        let mut differs = false;
+
+        for _ in 0..10 {
+            shake.update(0.03);
+            same.update(0.03);
+            other.update(0.03);
+
+            // seeded shakes are equal and stay inside of the maximum
+            assert_eq!(same.offset(), shake.offset());
+            assert!(shake.offset().x().abs() <= 16.0 && shake.offset().y().abs() <= 16.0);
+            differs |= other.offset() != shake.offset();
+        }
+
+        assert!(differs);
+
+        // the trauma decays
+        shake.update(1.0);
+        assert_eq!(0.0, shake.trauma());
+        assert_eq!(Point::default(), shake.offset());
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    path::Path,
+    rc::Rc,
+};
+
+use log::error;
+use orbtk::prelude::*;
+
+use crate::{Map, MapError};
+
+/// Shares one map between several tile maps, e.g. for split-screen with a camera for each player.
+///
+/// All clones refer to the same map, so cloning a shared map does not copy the map. Changes made
+/// through `borrow_mut` are seen by all tile maps that share it.
+#[derive(Clone, Default, Debug)]
+pub struct SharedMap {
+    map: Rc<RefCell<Map>>,
+}
+
+// Shared maps are equal if they share the same map.
+impl PartialEq for SharedMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_same(other)
+    }
+}
+
+into_property_source!(SharedMap: Map, &str, String);
+
+impl SharedMap {
+    /// Creates a new shared map that holds the given map.
+    pub fn new(map: Map) -> Self {
+        SharedMap {
+            map: Rc::new(RefCell::new(map)),
+        }
+    }
+
+    /// Loads the map from the given path.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MapError> {
+        Map::load(path).map(SharedMap::new)
+    }
+
+    /// Borrows the map.
+    pub fn borrow(&self) -> Ref<'_, Map> {
+        self.map.borrow()
+    }
+
+    /// Borrows the map mutable, e.g. to change its tiles.
+    pub fn borrow_mut(&self) -> RefMut<'_, Map> {
+        self.map.borrow_mut()
+    }
+
+    /// Replaces the map of all clones and returns the old map.
+    pub fn replace(&self, map: Map) -> Map {
+        self.map.replace(map)
+    }
+
+    /// Returns `true` if both share the same map.
+    pub fn is_same(&self, other: &SharedMap) -> bool {
+        Rc::ptr_eq(&self.map, &other.map)
+    }
+}
+
+impl From<Map> for SharedMap {
+    fn from(map: Map) -> Self {
+        SharedMap::new(map)
+    }
+}
+
+// Loads the map from the given path, e.g. for the map property of a tile map. Load errors are
+// logged and an empty map is used, use `SharedMap::load` to handle them.
+impl From<&str> for SharedMap {
+    fn from(path: &str) -> Self {
+        SharedMap::load(path).unwrap_or_else(|e| {
+            error!("could not load map {}: {}", path, e);
+            SharedMap::default()
+        })
+    }
+}
+
+impl From<String> for SharedMap {
+    fn from(path: String) -> Self {
+        SharedMap::from(path.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_shared_map() {
+        let map = SharedMap::from(
+            Map::from_ron_str(
+                "(layer_count: 1, row_count: 1, column_count: 2, tile_size: 8, blocked_tiles: [],
+                    layers: [(tiles: [0, 1])],
+                )",
+            )
+            .unwrap(),
+        );
+        let shared = map.clone();
+        let stamp = map.borrow().changes.stamp();
+
+        // changes are seen by all clones
+        shared.borrow_mut().set_tile(0, 1, 0, 3);
+        assert_eq!(3, map.borrow().get_tile(0, 0, 1));
+        assert_eq!(
+            vec![(1, 0)],
+            map.borrow()
+                .changes
+                .since(stamp)
+                .unwrap()
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(map, shared);
+        assert_ne!(map, SharedMap::new(map.borrow().clone()));
+
+        shared.replace(Map::default());
+        assert_eq!(0, map.borrow().column_count());
+
+        // load errors are returned or an empty map is used
+        assert!(SharedMap::load("does/not/exist.ron").is_err());
+        assert_eq!(
+            0,
+            SharedMap::from("does/not/exist.ron")
+                .borrow()
+                .column_count()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+/// The flag of a tile entry that flips the tile horizontally.
+pub const FLIP_X: u32 = 0x8000_0000;
+
+/// The flag of a tile entry that flips the tile vertically.
+pub const FLIP_Y: u32 = 0x4000_0000;
+
+/// The flag of a tile entry that flips the tile diagonally (swaps x and y).
+pub const FLIP_DIAGONAL: u32 = 0x2000_0000;
+
+/// All flip flags of a tile entry.
+pub const FLIP_FLAGS: u32 = FLIP_X | FLIP_Y | FLIP_DIAGONAL;
+
+/// Gets the tile id of a tile entry without its flip flags.
+pub fn tile_id(entry: i32) -> i32 {
+    Tile::from_entry(entry).id
+}
+
+/// Describes a tile entry of a layer, the tile id and how the tile is flipped.
+///
+/// Layers store the entries as `i32`. Like the global ids of Tiled the flags are stored in the
+/// three highest bits. A diagonal flip is applied before the horizontal and vertical flip, e.g. a
+/// rotation of 90° clockwise is a diagonal and a horizontal flip.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tile {
+    /// The id of the tile, -1 is empty.
+    pub id: i32,
+
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub diagonal: bool,
+}
+
+impl Default for Tile {
+    fn default() -> Self {
+        Tile::new(-1)
+    }
+}
+
+impl Tile {
+    /// Creates a new tile without flip flags.
+    pub fn new(id: i32) -> Self {
+        Tile {
+            id,
+            flip_x: false,
+            flip_y: false,
+            diagonal: false,
+        }
+    }
+
+    /// Creates a tile from an entry of a layer.
+    pub fn from_entry(entry: i32) -> Self {
+        if entry == -1 {
+            return Tile::default();
+        }
+
+        let entry = entry as u32;
+
+        Tile {
+            id: (entry & !FLIP_FLAGS) as i32,
+            flip_x: entry & FLIP_X != 0,
+            flip_y: entry & FLIP_Y != 0,
+            diagonal: entry & FLIP_DIAGONAL != 0,
+        }
+    }
+
+    /// Converts the tile to an entry of a layer.
+    pub fn to_entry(self) -> i32 {
+        if self.is_empty() {
+            return -1;
+        }
+
+        let mut entry = self.id as u32 & !FLIP_FLAGS;
+
+        if self.flip_x {
+            entry |= FLIP_X;
+        }
+
+        if self.flip_y {
+            entry |= FLIP_Y;
+        }
+
+        if self.diagonal {
+            entry |= FLIP_DIAGONAL;
+        }
+
+        entry as i32
+    }
+
+    /// Returns `true` if the tile is empty.
+    pub fn is_empty(&self) -> bool {
+        self.id < 0
+    }
+
+    /// Returns `true` if any flip flag is set.
+    pub fn is_flipped(&self) -> bool {
+        self.flip_x || self.flip_y || self.diagonal
+    }
+
+    /// Returns the tile flipped horizontally.
+    pub fn flipped_x(mut self) -> Self {
+        self.flip_x = !self.flip_x;
+        self
+    }
+
+    /// Returns the tile flipped vertically.
+    pub fn flipped_y(mut self) -> Self {
+        self.flip_y = !self.flip_y;
+        self
+    }
+
+    /// Returns the tile rotated by 90° clockwise.
+    pub fn rotated(self) -> Self {
+        Tile {
+            id: self.id,
+            flip_x: !self.flip_y,
+            flip_y: self.flip_x,
+            diagonal: !self.diagonal,
+        }
+    }
+
+    /// Gets the position inside of the unflipped tile image that is drawn at the given position
+    /// of the flipped tile with the given size. Diagonal flips expect square tiles.
+    pub fn source_position(&self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        let x = if self.flip_x { width - 1 - x } else { x };
+        let y = if self.flip_y { height - 1 - y } else { y };
+
+        if self.diagonal {
+            (y, x)
+        } else {
+            (x, y)
+        }
+    }
+}
+
+impl From<i32> for Tile {
+    fn from(entry: i32) -> Self {
+        Tile::from_entry(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_entry() {
+        let tile = Tile::new(5).flipped_x();
+        assert_eq!(5 | FLIP_X as i32, tile.to_entry());
+        assert_eq!(tile, Tile::from_entry(tile.to_entry()));
+        assert_eq!(5, tile_id(tile.to_entry()));
+
+        assert_eq!(-1, Tile::default().to_entry());
+        assert!(Tile::from_entry(-1).is_empty());
+        assert!(!Tile::from_entry(3).is_flipped());
+    }
+
+    #[test]
+    fn test_source_position() {
+        // 2 x 2 tile: 0 1
+        //             2 3
+        let pixel = |tile: Tile, x, y| {
+            let (x, y) = tile.source_position(x, y, 2, 2);
+            y * 2 + x
+        };
+
+        let tile = Tile::new(0);
+        assert_eq!(1, pixel(tile, 1, 0));
+        assert_eq!(0, pixel(tile.flipped_x(), 1, 0));
+        assert_eq!(3, pixel(tile.flipped_y(), 1, 0));
+
+        // rotated clockwise the left column becomes the top row
+        assert_eq!(2, pixel(tile.rotated(), 0, 0));
+        assert_eq!(0, pixel(tile.rotated(), 1, 0));
+        assert_eq!(3, pixel(tile.rotated().rotated(), 0, 0));
+        assert_eq!(tile, tile.rotated().rotated().rotated().rotated());
+    }
+}
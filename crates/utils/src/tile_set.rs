@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{animation::current_frame, object::polygon_contains, Frame};
+
+/// Describes the side of a tile that is covered by a half collision.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Describes the collision shape of a tile.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Collision {
+    /// The tile could be passed.
+    None,
+
+    /// The whole tile is blocked.
+    Full,
+
+    /// The half of the tile at the given side is blocked.
+    Half(Side),
+
+    /// The tile is blocked below a line from the left to the right edge. The heights are
+    /// relative to the tile height from 0.0 (bottom) to 1.0 (top).
+    Slope { left: f64, right: f64 },
+
+    /// The tile is blocked inside of the polygon. The points are in pixels relative to the top
+    /// left corner of the tile.
+    Polygon(Vec<(f64, f64)>),
+}
+
+impl Default for Collision {
+    fn default() -> Self {
+        Collision::None
+    }
+}
+
+impl Collision {
+    /// Returns `true` if any part of the tile is blocked.
+    pub fn is_blocking(&self) -> bool {
+        match self {
+            Collision::None => false,
+            Collision::Polygon(points) => points.len() > 2,
+            _ => true,
+        }
+    }
+
+    /// Checks if the given position inside of a tile with the given size is blocked. The position
+    /// is relative to the top left corner of the tile.
+    pub fn contains(&self, x: f64, y: f64, width: f64, height: f64) -> bool {
+        match self {
+            Collision::None => false,
+            Collision::Full => true,
+            Collision::Half(Side::Top) => y < height / 2.0,
+            Collision::Half(Side::Bottom) => y >= height / 2.0,
+            Collision::Half(Side::Left) => x < width / 2.0,
+            Collision::Half(Side::Right) => x >= width / 2.0,
+            Collision::Slope { left, right } => {
+                let t = if width > 0.0 { x / width } else { 0.0 };
+                let surface = height * (1.0 - (left + (right - left) * t));
+
+                y >= surface
+            }
+            Collision::Polygon(points) => polygon_contains(points, x, y),
+        }
+    }
+}
+
+/// Describes the additional data of a tile in a tile set.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TileData {
+    /// The id of the tile inside of its tile set.
+    pub id: u32,
+
+    /// The collision shape of the tile.
+    pub collision: Collision,
+
+    /// The terrain type of the tile, e.g. `water` or `grass`.
+    pub terrain: Option<String>,
+
+    /// Free-form properties of the tile.
+    pub properties: BTreeMap<String, String>,
+
+    /// The frames of an animated tile. The animation loops and tiles without frames are static.
+    pub animation: Vec<Frame>,
+}
+
+impl TileData {
+    /// Gets the property with the given key.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+}
+
+/// Describes the layout of a tile set image and the additional data of its tiles.
+///
+/// The tiles are arranged in rows of `columns` tiles. The first tile starts after `margin`
+/// pixels and the tiles are separated by `spacing` pixels. Like the global ids of Tiled, the
+/// tiles of a map refer to the tile set with the highest `first_id` that is not greater than
+/// the tile id.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TileSet {
+    /// The name of the tile set.
+    pub name: String,
+
+    /// The map tile id of the first tile of the tile set.
+    pub first_id: u32,
+
+    /// The path of the tile set image.
+    pub image: String,
+
+    pub tile_width: u32,
+    pub tile_height: u32,
+
+    /// The space in pixels around the tiles of the image.
+    pub margin: u32,
+
+    /// The space in pixels between the tiles of the image.
+    pub spacing: u32,
+
+    /// The number of tile columns of the image. If it is 0, the columns are calculated from the
+    /// image width.
+    pub columns: u32,
+
+    /// Tiles with additional data, tiles without data could be omitted.
+    pub tiles: Vec<TileData>,
+}
+
+impl TileSet {
+    /// Creates a new tile set with the given image path and tile size.
+    pub fn new(image: impl Into<String>, tile_width: u32, tile_height: u32) -> Self {
+        TileSet {
+            image: image.into(),
+            tile_width,
+            tile_height,
+            ..TileSet::default()
+        }
+    }
+
+    /// Gets the number of tile columns for an image with the given width.
+    pub fn columns(&self, image_width: u32) -> u32 {
+        if self.columns > 0 {
+            return self.columns;
+        }
+
+        let stride = self.tile_width + self.spacing;
+
+        if stride == 0 || image_width < 2 * self.margin {
+            return 0;
+        }
+
+        (image_width - 2 * self.margin + self.spacing) / stride
+    }
+
+    /// Gets the position of the tile with the given id in an image with the given width.
+    pub fn tile_position(&self, tile: u32, image_width: u32) -> Option<(u32, u32)> {
+        let columns = self.columns(image_width);
+
+        if columns == 0 {
+            return None;
+        }
+
+        Some((
+            self.margin + (tile % columns) * (self.tile_width + self.spacing),
+            self.margin + (tile / columns) * (self.tile_height + self.spacing),
+        ))
+    }
+
+    /// Gets the additional data of the tile with the given id.
+    pub fn tile(&self, tile: u32) -> Option<&TileData> {
+        self.tiles.iter().find(|t| t.id == tile)
+    }
+
+    /// Gets the id of the tile that is drawn for the given tile after the elapsed milliseconds.
+    pub fn animated_tile(&self, tile: u32, elapsed: f64) -> u32 {
+        self.tile(tile)
+            .and_then(|t| current_frame(&t.animation, elapsed))
+            .map_or(tile, |f| f.tile)
+    }
+
+    /// Gets the collision shape of the tile with the given id.
+    pub fn collision(&self, tile: u32) -> &Collision {
+        match self.tile(tile) {
+            Some(data) => &data.collision,
+            None => &Collision::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_tile_position() {
+        let mut tile_set = TileSet::new("tiles.png", 16, 16);
+        assert_eq!(Some((32, 16)), tile_set.tile_position(6, 64));
+
+        tile_set.margin = 1;
+        tile_set.spacing = 2;
+        assert_eq!(3, tile_set.columns(56));
+        assert_eq!(Some((19, 19)), tile_set.tile_position(4, 56));
+
+        tile_set.columns = 2;
+        assert_eq!(Some((1, 37)), tile_set.tile_position(4, 56));
+    }
+
+    #[test]
+    fn test_collision_contains() {
+        assert!(Collision::Full.contains(1.0, 1.0, 16.0, 16.0));
+        assert!(!Collision::None.contains(1.0, 1.0, 16.0, 16.0));
+        assert!(Collision::Half(Side::Bottom).contains(1.0, 12.0, 16.0, 16.0));
+        assert!(!Collision::Half(Side::Bottom).contains(1.0, 4.0, 16.0, 16.0));
+        assert!(Collision::Half(Side::Right).contains(12.0, 1.0, 16.0, 16.0));
+
+        // rising from the bottom left to the top right
+        let slope = Collision::Slope {
+            left: 0.0,
+            right: 1.0,
+        };
+        assert!(slope.contains(15.0, 2.0, 16.0, 16.0));
+        assert!(!slope.contains(1.0, 2.0, 16.0, 16.0));
+        assert!(slope.contains(1.0, 15.9, 16.0, 16.0));
+
+        let polygon = Collision::Polygon(vec![(0.0, 0.0), (16.0, 0.0), (0.0, 16.0)]);
+        assert!(polygon.contains(2.0, 2.0, 16.0, 16.0));
+        assert!(!polygon.contains(14.0, 14.0, 16.0, 16.0));
+    }
+}
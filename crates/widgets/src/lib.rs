@@ -7,6 +7,8 @@ pub use orbgame_api::prelude as api;
 pub use orbgame_utils::prelude as utils;
 pub use orbtk::widgets::*;
 
+pub use self::mini_map::*;
 pub use self::tile_map::*;
 
+mod mini_map;
 mod tile_map;
@@ -0,0 +1,119 @@
+use crate::{api::prelude::MiniMapRenderObject, prelude::*, tile_map::MouseInput, utils::*};
+
+/// Centers the camera of the `MiniMap` on the position of the map that is clicked or dragged
+/// over.
+#[derive(Default, AsAny)]
+pub struct MiniMapState {
+    input: Vec<MouseInput>,
+    pressed: bool,
+}
+
+impl MiniMapState {
+    fn mouse(&mut self, input: MouseInput) {
+        self.input.push(input);
+    }
+
+    // Centers the camera on the last position of the mouse on the map while the minimap is
+    // pressed.
+    fn move_camera(&mut self, ctx: &mut Context) {
+        let mut target = None;
+
+        for input in self.input.drain(..) {
+            match input {
+                MouseInput::Down(p) => {
+                    self.pressed = true;
+                    target = Some(p);
+                }
+                MouseInput::Up(_) => self.pressed = false,
+                MouseInput::Move(p) if self.pressed => target = Some(p),
+                MouseInput::Move(_) => {}
+            }
+        }
+
+        let target = match target {
+            Some(target) => target,
+            None => return,
+        };
+
+        let camera = {
+            let widget = ctx.widget();
+            let buffer = widget.get::<MiniMapBuffer>("buffer");
+            let map = widget.get::<SharedMap>("map").borrow();
+
+            let position = match buffer.screen_to_world(&map, target) {
+                Some(position) => position,
+                None => return,
+            };
+
+            let mut camera = widget.clone::<Camera>("camera");
+            camera.center_on(position);
+            camera
+        };
+
+        ctx.widget().set("camera", camera);
+    }
+}
+
+impl State for MiniMapState {
+    fn update(&mut self, _: &mut Registry, ctx: &mut Context) {
+        self.move_camera(ctx);
+    }
+}
+
+widget!(
+    /// The `MiniMap` widget draws an overview of a tile map with a block of color for each tile,
+    /// the view of the camera and markers of entities. Clicking the minimap moves the camera.
+    ///
+    /// To show the overview of a `TileMap` share its map, camera and images with the minimap,
+    /// e.g. `MiniMap::new().map(tile_map).camera(tile_map)`.
+    MiniMap<MiniMapState>: MouseHandler {
+        /// Sets or shares the map.
+        map: SharedMap,
+
+        /// Sets or shares the camera whose view is drawn and that is moved by clicks.
+        camera: Camera,
+
+        /// Sets or shares the image of the tile map. It is used for maps without tile sets.
+        image: SharedImage,
+
+        /// Sets or shares the images of the tile sets of the map.
+        images: TileImages,
+
+        /// Sets or shares the colors of the tiles. Tiles without a color are drawn with the
+        /// average color of their image.
+        colors: TileColors,
+
+        /// Sets or shares the markers of entities, e.g. the players.
+        markers: MapMarkers,
+
+        /// Holds the drawn tiles of the minimap between frames.
+        buffer: MiniMapBuffer
+    }
+);
+
+impl Template for MiniMap {
+    fn template(self, id: Entity, _: &mut BuildContext) -> Self {
+        self.name("MiniMap")
+            .on_mouse_down(move |states, mouse| {
+                states
+                    .get_mut::<MiniMapState>(id)
+                    .mouse(MouseInput::Down(mouse.position));
+                false
+            })
+            .on_mouse_up(move |states, mouse| {
+                states
+                    .get_mut::<MiniMapState>(id)
+                    .mouse(MouseInput::Up(mouse.position));
+            })
+            .on_mouse_move(move |states, position| {
+                states
+                    .get_mut::<MiniMapState>(id)
+                    .mouse(MouseInput::Move(position));
+                false
+            })
+    }
+
+    fn render_object(&self) -> Box<dyn RenderObject> {
+        Box::new(MiniMapRenderObject)
+    }
+}
@@ -1,22 +1,370 @@
-use crate::{api::prelude::TileMapRenderObject, prelude::*, utils::*};
+use std::rc::Rc;
+
+use log::error;
+
+use crate::{
+    api::{prelude::TileMapRenderObject, render::Image},
+    prelude::*,
+    utils::*,
+};
+
+/// Describes a mouse input on a tile of a `TileMap`. Tiles are given by column and row, the
+/// same on each layer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TileEvent {
+    /// The tile was pressed and released.
+    Click((usize, usize)),
+
+    /// The mouse moved onto the tile.
+    Hover((usize, usize)),
+
+    /// The mouse was pressed on the first tile and moved onto the second tile.
+    Drag((usize, usize), (usize, usize)),
+}
+
+impl Event for TileEvent {}
+
+pub type TileHandlerFn = dyn Fn(&mut StatesContext, TileEvent) -> bool + 'static;
+
+/// Handles the `TileEvent`s of a `TileMap`.
+pub struct TileEventHandler {
+    handler: Rc<TileHandlerFn>,
+}
+
+impl Into<Rc<dyn EventHandler>> for TileEventHandler {
+    fn into(self) -> Rc<dyn EventHandler> {
+        Rc::new(self)
+    }
+}
+
+impl EventHandler for TileEventHandler {
+    fn handle_event(&self, states: &mut StatesContext, event: &EventBox) -> bool {
+        if let Ok(event) = event.downcast_ref::<TileEvent>() {
+            return (self.handler)(states, *event);
+        }
+
+        false
+    }
+
+    fn handles_event(&self, event: &EventBox) -> bool {
+        event.is_type::<TileEvent>()
+    }
+}
+
+// A mouse input on the widget that is handled on the next update.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum MouseInput {
+    Down(Point),
+    Up(Point),
+    Move(Point),
+}
+
+/// Loads the images of the tile sets and the image layers of the map if no images are set on the
+/// `TileMap`, advances the tile animations with the clock, fits the camera to the map and converts
+/// the mouse input to tile events.
+#[derive(Default, AsAny)]
+pub struct TileMapState {
+    // the image paths of the tile sets and the image layers that are loaded by the state
+    image_paths: Vec<String>,
+    layer_image_paths: Vec<String>,
+
+    input: Vec<MouseInput>,
+    pressed: Option<(usize, usize)>,
+    dragged: Option<(usize, usize)>,
+    hovered: Option<(usize, usize)>,
+}
+
+impl TileMapState {
+    fn mouse(&mut self, input: MouseInput) {
+        self.input.push(input);
+    }
+
+    // Converts the mouse input since the last update to tile events of the widget. The mouse
+    // position is converted to the world by the camera.
+    fn handle_input(&mut self, ctx: &mut Context) {
+        if self.input.is_empty() {
+            return;
+        }
+
+        let mut events = vec![];
+
+        {
+            let widget = ctx.widget();
+            let bounds = widget.get::<TileMapBuffer>("buffer").bounds();
+            let camera = widget.get::<Camera>("camera");
+            let map = widget.get::<SharedMap>("map").borrow();
+            let tile_at = |p: Point| {
+                map.tile_at(
+                    camera.screen_to_world(Point::new(p.x() - bounds.x(), p.y() - bounds.y())),
+                )
+            };
+
+            for input in self.input.drain(..) {
+                match input {
+                    MouseInput::Down(p) => {
+                        self.pressed = tile_at(p);
+                        self.dragged = self.pressed;
+                    }
+                    MouseInput::Up(p) => {
+                        if let Some(tile) = tile_at(p) {
+                            if self.pressed == Some(tile) {
+                                events.push(TileEvent::Click(tile));
+                            }
+                        }
+
+                        self.pressed = None;
+                        self.dragged = None;
+                    }
+                    MouseInput::Move(p) => {
+                        let tile = tile_at(p);
+
+                        if tile != self.hovered {
+                            if let Some(tile) = tile {
+                                events.push(TileEvent::Hover(tile));
+                            }
+
+                            self.hovered = tile;
+                        }
+
+                        if let (Some(from), Some(to)) = (self.pressed, tile) {
+                            if self.dragged != Some(to) {
+                                events.push(TileEvent::Drag(from, to));
+                                self.dragged = Some(to);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let entity = ctx.entity;
+
+        for event in events {
+            ctx.push_event_strategy_by_entity(event, entity, EventStrategy::Direct);
+        }
+    }
+
+    // Takes the time of the clock over to the elapsed property. To request a redraw only while
+    // animated tiles are on screen, the elapsed property is only set if one of them changes.
+    fn animate(&mut self, ctx: &mut Context) {
+        let clock = match ctx.widget().try_clone::<AnimationClock>("clock") {
+            Some(clock) => clock,
+            None => return,
+        };
+
+        let now = clock.elapsed();
+        let changed = {
+            let widget = ctx.widget();
+            let elapsed = *widget.get::<f64>("elapsed");
+
+            let map = widget.get::<SharedMap>("map").borrow();
+
+            map.animation_changed(widget.get::<Camera>("camera"), elapsed, now)
+        };
+
+        if changed {
+            ctx.widget().set("elapsed", now);
+        }
+    }
+
+    // Keeps a camera that fits the map at the size of the widget and inside of the map, e.g.
+    // after the widget is resized or the map is replaced.
+    fn fit_camera(&mut self, ctx: &mut Context) {
+        let camera = {
+            let widget = ctx.widget();
+            let camera = widget.get::<Camera>("camera");
+
+            if !camera.fit_map() {
+                return;
+            }
+
+            let bounds = widget.get::<Rectangle>("bounds");
+            let mut fitted = camera.clone();
+            fitted.set_size(bounds.width(), bounds.height());
+            fitted.constrain_to_map(&widget.get::<SharedMap>("map").borrow());
+
+            if fitted == *camera {
+                return;
+            }
+
+            fitted
+        };
+
+        ctx.widget().set("camera", camera);
+    }
+}
+
+// Loads the images from the given paths. Images that could not be loaded are logged and leave a
+// gap like tile sets without an image, so the indices still match the tile sets.
+fn load_images(paths: &[String]) -> Vec<Option<Image>> {
+    paths
+        .iter()
+        .map(|path| {
+            if path.is_empty() {
+                return None;
+            }
+
+            load_image(path)
+                .map_err(|e| error!("could not load tile map image: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+impl State for TileMapState {
+    fn init(&mut self, registry: &mut Registry, ctx: &mut Context) {
+        self.update(registry, ctx);
+    }
+
+    fn update(&mut self, _: &mut Registry, ctx: &mut Context) {
+        self.handle_input(ctx);
+        self.animate(ctx);
+
+        // images that are set from outside are not replaced
+        if self.image_paths.is_empty()
+            && self.layer_image_paths.is_empty()
+            && !ctx.widget().get::<TileImages>("images").is_empty()
+        {
+            return;
+        }
+
+        let (image_paths, layer_image_paths): (Vec<String>, Vec<String>) = {
+            let widget = ctx.widget();
+            let map = widget.get::<SharedMap>("map").borrow();
+
+            (
+                map.tile_sets.iter().map(|t| t.image.clone()).collect(),
+                map.image_layers.iter().map(|l| l.image.clone()).collect(),
+            )
+        };
+
+        if image_paths == self.image_paths && layer_image_paths == self.layer_image_paths {
+            return;
+        }
+
+        // the images loaded for a replaced map are cleared, so they are not drawn for the new one
+        if image_paths
+            .iter()
+            .chain(layer_image_paths.iter())
+            .all(|p| p.is_empty())
+        {
+            if !self.image_paths.is_empty() || !self.layer_image_paths.is_empty() {
+                ctx.widget().set("images", TileImages::default());
+                self.image_paths.clear();
+                self.layer_image_paths.clear();
+            }
+
+            return;
+        }
+
+        ctx.widget().set(
+            "images",
+            TileImages::new(load_images(&image_paths))
+                .with_layer_images(load_images(&layer_image_paths)),
+        );
+        self.image_paths = image_paths;
+        self.layer_image_paths = layer_image_paths;
+    }
+
+    fn update_post_layout(&mut self, _: &mut Registry, ctx: &mut Context) {
+        self.fit_camera(ctx);
+    }
+}
 
 widget!(
     /// The `TileMap` widget is use to draw a tile map to the screen an to navigate on the map with a camera.
-    TileMap {
-        /// Sets or shares the map property.
-        map: Map,
+    TileMap<TileMapState>: MouseHandler {
+        /// Sets or shares the map. Tile maps that get clones of the same `SharedMap` show one map,
+        /// e.g. for split-screen, each with its own camera and bounds.
+        map: SharedMap,
 
-        /// Sets or shares the camera of the tile map.
+        /// Sets or shares the camera of the tile map. A camera that fits the map is resized with
+        /// the widget and constrained to the map.
         camera: Camera,
 
-        /// Sets or shares the image of the tile map.
-        image: Image
+        /// Sets or shares the image of the tile map. It is used for maps without tile sets.
+        image: SharedImage,
+
+        /// Sets or shares the images of the tile sets of the map. If it is not set the images are
+        /// loaded from the image paths of the tile sets.
+        images: TileImages,
+
+        /// Sets or shares the clock of the tile animations. It is advanced by the game.
+        clock: AnimationClock,
+
+        /// Sets or shares the elapsed time of the tile animations in milliseconds.
+        elapsed: f64,
+
+        /// Holds the layers of the tile map composited into one render target between frames.
+        buffer: TileMapBuffer
     }
 );
 
+impl TileMap {
+    /// Inserts a handler that is called with the column and the row of a clicked tile.
+    pub fn on_tile_click<H: Fn(&mut StatesContext, (usize, usize)) -> bool + 'static>(
+        self,
+        handler: H,
+    ) -> Self {
+        self.insert_handler(TileEventHandler {
+            handler: Rc::new(move |states, event| match event {
+                TileEvent::Click(tile) => handler(states, tile),
+                _ => false,
+            }),
+        })
+    }
+
+    /// Inserts a handler that is called with the column and the row of the tile the mouse moved
+    /// onto.
+    pub fn on_tile_hover<H: Fn(&mut StatesContext, (usize, usize)) -> bool + 'static>(
+        self,
+        handler: H,
+    ) -> Self {
+        self.insert_handler(TileEventHandler {
+            handler: Rc::new(move |states, event| match event {
+                TileEvent::Hover(tile) => handler(states, tile),
+                _ => false,
+            }),
+        })
+    }
+
+    /// Inserts a handler that is called with the pressed tile and the tile the mouse was dragged
+    /// onto.
+    pub fn on_tile_drag<
+        H: Fn(&mut StatesContext, (usize, usize), (usize, usize)) -> bool + 'static,
+    >(
+        self,
+        handler: H,
+    ) -> Self {
+        self.insert_handler(TileEventHandler {
+            handler: Rc::new(move |states, event| match event {
+                TileEvent::Drag(from, to) => handler(states, from, to),
+                _ => false,
+            }),
+        })
+    }
+}
+
 impl Template for TileMap {
-    fn template(self, _: Entity, _: &mut BuildContext) -> Self {
+    fn template(self, id: Entity, _: &mut BuildContext) -> Self {
         self.name("TileMap")
+            .on_mouse_down(move |states, mouse| {
+                states
+                    .get_mut::<TileMapState>(id)
+                    .mouse(MouseInput::Down(mouse.position));
+                false
+            })
+            .on_mouse_up(move |states, mouse| {
+                states
+                    .get_mut::<TileMapState>(id)
+                    .mouse(MouseInput::Up(mouse.position));
+            })
+            .on_mouse_move(move |states, position| {
+                states
+                    .get_mut::<TileMapState>(id)
+                    .mouse(MouseInput::Move(position));
+                false
+            })
     }
 
     fn render_object(&self) -> Box<dyn RenderObject> {
@@ -51,26 +51,31 @@ widget!(MapView<MapViewState> : KeyDownHandler);
 
 impl Template for MapView {
     fn template(self, id: Entity, ctx: &mut BuildContext) -> Self {
+        let tile_map = TileMap::new()
+            .camera(CameraBuilder::new().fit_map(true).build())
+            .map("res/dungeon/dungeon.ron")
+            .image("res/dungeon/tile_set.png")
+            .build(ctx);
+
         self.name("MapView")
             .child(
                 Container::new()
                     .style("container")
                     .child(
                         Grid::new()
+                            .child(tile_map)
                             .child(
-                                TileMap::new()
-                                    .camera(
-                                        CameraBuilder::new()
-                                            .x(0.0)
-                                            .y(0.0)
-                                            .width(352.0)
-                                            .height(352.0)
-                                            .max_width(352.0)
-                                            .max_height(352.0)
-                                            .build(),
-                                    )
-                                    .map("res/dungeon/dungeon.ron")
-                                    .image("res/dungeon/tile_set.png")
+                                // shows the whole dungeon, clicking it moves the camera
+                                MiniMap::new()
+                                    .map(tile_map)
+                                    .camera(tile_map)
+                                    .image(tile_map)
+                                    .images(tile_map)
+                                    .width(160.0)
+                                    .height(120.0)
+                                    .h_align("end")
+                                    .v_align("start")
+                                    .margin(4.0)
                                     .build(ctx),
                             )
                             .child(
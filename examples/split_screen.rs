@@ -0,0 +1,44 @@
+use orbgame::prelude::*;
+
+fn main() {
+    // both views show the same map, it is loaded once and not copied
+    let map = SharedMap::from("res/dungeon/dungeon.ron");
+
+    Game::new()
+        .window(move |ctx| {
+            Window::new()
+                .title("OrbGame - split screen example")
+                .position((100.0, 100.0))
+                .size(800.0, 600.0)
+                .child(
+                    Grid::new()
+                        .columns(Columns::create().column("*").column("*").build())
+                        .child(
+                            TileMap::new()
+                                .camera(CameraBuilder::new().fit_map(true).build())
+                                .map(map.clone())
+                                .image("res/dungeon/tile_set.png")
+                                .attach(Grid::column(0))
+                                .build(ctx),
+                        )
+                        .child(
+                            TileMap::new()
+                                .camera(
+                                    CameraBuilder::new()
+                                        .x(320.0)
+                                        .y(160.0)
+                                        .fit_map(true)
+                                        .build(),
+                                )
+                                .map(map.clone())
+                                .image("res/dungeon/tile_set.png")
+                                .margin((4.0, 0.0, 0.0, 0.0))
+                                .attach(Grid::column(1))
+                                .build(ctx),
+                        )
+                        .build(ctx),
+                )
+                .build(ctx)
+        })
+        .run();
+}